@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use slut::{dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+use crate::loss::Real;
+use crate::{N, Params};
+
+// Each node records up to two parents with the local partial derivative
+// towards them. Unused slots have a zero weight.
+#[derive(Clone, Copy)]
+struct Node {
+    parents: [(usize, f64); 2],
+}
+
+/// Records every operation on tracked values so a single backward pass
+/// yields the gradient of an output with respect to all inputs.
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>,
+}
+
+/// A scalar tracked on a `Tape`.
+#[derive(Clone, Copy)]
+pub struct Var<'t> {
+    tape: &'t Tape,
+    idx: usize,
+    value: f64,
+}
+
+/// A `Params` whose components are tracked.
+#[derive(Clone, Copy)]
+pub struct VarVector<'t> {
+    vars: [Var<'t>; N],
+}
+
+/// Adjoints of every node on the tape, produced by `Var::backward`.
+pub struct Grads {
+    adjoints: Vec<f64>,
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Tape { nodes: RefCell::new(Vec::new()) }
+    }
+
+    fn push(&self, value: f64, parents: [(usize, f64); 2]) -> Var<'_> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { parents });
+        Var { tape: self, idx: nodes.len() - 1, value }
+    }
+
+    /// Track a new input.
+    pub fn var(&self, value: Scalar<f64, Dimensionless>) -> Var<'_> {
+        self.push(value.raw(), [(0, 0.0), (0, 0.0)])
+    }
+
+    /// A value the output does not need a gradient for.
+    pub fn constant(&self, value: Scalar<f64, Dimensionless>) -> Var<'_> {
+        self.var(value)
+    }
+
+    /// Track every component of `v`.
+    pub fn vector(&self, v: &Params) -> VarVector<'_> {
+        VarVector { vars: std::array::from_fn(|k| self.var(v.get_at(0, k, 0))) }
+    }
+}
+
+impl Default for Tape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'t> Var<'t> {
+    pub fn value(&self) -> Scalar<f64, Dimensionless> {
+        dless!(self.value)
+    }
+
+    pub fn raw(&self) -> f64 {
+        self.value
+    }
+
+    fn unary(self, value: f64, d: f64) -> Var<'t> {
+        self.tape.push(value, [(self.idx, d), (0, 0.0)])
+    }
+
+    fn binary(self, other: Var<'t>, value: f64, da: f64, db: f64) -> Var<'t> {
+        self.tape.push(value, [(self.idx, da), (other.idx, db)])
+    }
+
+    pub fn powi(self, n: i32) -> Var<'t> {
+        let d = if n == 0 { 0.0 } else { n as f64 * self.value.powi(n - 1) };
+        self.unary(self.value.powi(n), d)
+    }
+
+    /// Absolute value. The derivative at zero is taken as zero.
+    pub fn mag(self) -> Var<'t> {
+        let d = if self.value > 0.0 { 1.0 } else if self.value < 0.0 { -1.0 } else { 0.0 };
+        self.unary(self.value.abs(), d)
+    }
+
+    pub fn exp(self) -> Var<'t> {
+        let e = self.value.exp();
+        self.unary(e, e)
+    }
+
+    pub fn ln(self) -> Var<'t> {
+        self.unary(self.value.ln(), 1.0 / self.value)
+    }
+
+    pub fn tanh(self) -> Var<'t> {
+        let t = self.value.tanh();
+        self.unary(t, 1.0 - t * t)
+    }
+
+    pub fn sqrt(self) -> Var<'t> {
+        let r = self.value.sqrt();
+        self.unary(r, 0.5 / r)
+    }
+
+    /// Propagate adjoints from this value back to the start of the tape.
+    pub fn backward(&self) -> Grads {
+        let nodes = self.tape.nodes.borrow();
+        let mut adjoints = vec![0.0f64; nodes.len()];
+        adjoints[self.idx] = 1.0;
+
+        for i in (0..=self.idx).rev() {
+            let a = adjoints[i];
+            if a == 0.0 {
+                continue;
+            }
+            for (p, d) in nodes[i].parents {
+                adjoints[p] += a * d;
+            }
+        }
+        Grads { adjoints }
+    }
+}
+
+impl<'t> VarVector<'t> {
+    pub fn get(&self, k: usize) -> Var<'t> {
+        self.vars[k]
+    }
+
+    /// Tracked equivalent of `dot!(self, other)` against a constant vector.
+    pub fn dot(&self, other: &Params) -> Var<'t> {
+        let mut acc = self.vars[0] * other.get_at(0, 0, 0);
+        for k in 1..N {
+            acc = acc + self.vars[k] * other.get_at(0, k, 0);
+        }
+        acc
+    }
+
+    pub fn norm(&self) -> Var<'t> {
+        let mut acc = self.vars[0] * self.vars[0];
+        for k in 1..N {
+            acc = acc + self.vars[k] * self.vars[k];
+        }
+        let n = acc.value.sqrt();
+        let d = if n > 0.0 { 0.5 / n } else { 0.0 };
+        acc.unary(n, d)
+    }
+}
+
+impl Grads {
+    pub fn wrt(&self, v: Var) -> Scalar<f64, Dimensionless> {
+        dless!(self.adjoints[v.idx])
+    }
+
+    pub fn wrt_vector(&self, v: &VarVector) -> Params {
+        let mut g = Params::zero();
+        for k in 0..N {
+            g.set_at(0, k, 0, self.wrt(v.vars[k]));
+        }
+        g
+    }
+}
+
+impl<'t> Add for Var<'t> {
+    type Output = Var<'t>;
+    fn add(self, rhs: Var<'t>) -> Var<'t> {
+        self.binary(rhs, self.value + rhs.value, 1.0, 1.0)
+    }
+}
+
+impl<'t> Sub for Var<'t> {
+    type Output = Var<'t>;
+    fn sub(self, rhs: Var<'t>) -> Var<'t> {
+        self.binary(rhs, self.value - rhs.value, 1.0, -1.0)
+    }
+}
+
+impl<'t> Mul for Var<'t> {
+    type Output = Var<'t>;
+    fn mul(self, rhs: Var<'t>) -> Var<'t> {
+        self.binary(rhs, self.value * rhs.value, rhs.value, self.value)
+    }
+}
+
+impl<'t> Div for Var<'t> {
+    type Output = Var<'t>;
+    fn div(self, rhs: Var<'t>) -> Var<'t> {
+        let v = self.value / rhs.value;
+        self.binary(rhs, v, 1.0 / rhs.value, -v / rhs.value)
+    }
+}
+
+impl<'t> Neg for Var<'t> {
+    type Output = Var<'t>;
+    fn neg(self) -> Var<'t> {
+        self.unary(-self.value, -1.0)
+    }
+}
+
+impl<'t> Real for Var<'t> {
+    fn raw(&self) -> f64 {
+        self.value
+    }
+
+    fn lift(&self, v: f64) -> Self {
+        self.tape.constant(dless!(v))
+    }
+
+    fn exp(self) -> Self {
+        Var::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        Var::ln(self)
+    }
+
+    fn tanh(self) -> Self {
+        Var::tanh(self)
+    }
+
+    fn sqrt(self) -> Self {
+        Var::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        self.mag()
+    }
+}
+
+// Mixing tracked values with plain slut scalars
+impl<'t> Add<Scalar<f64, Dimensionless>> for Var<'t> {
+    type Output = Var<'t>;
+    fn add(self, rhs: Scalar<f64, Dimensionless>) -> Var<'t> {
+        self.unary(self.value + rhs.raw(), 1.0)
+    }
+}
+
+impl<'t> Sub<Scalar<f64, Dimensionless>> for Var<'t> {
+    type Output = Var<'t>;
+    fn sub(self, rhs: Scalar<f64, Dimensionless>) -> Var<'t> {
+        self.unary(self.value - rhs.raw(), 1.0)
+    }
+}
+
+impl<'t> Mul<Scalar<f64, Dimensionless>> for Var<'t> {
+    type Output = Var<'t>;
+    fn mul(self, rhs: Scalar<f64, Dimensionless>) -> Var<'t> {
+        self.unary(self.value * rhs.raw(), rhs.raw())
+    }
+}
+
+impl<'t> Div<Scalar<f64, Dimensionless>> for Var<'t> {
+    type Output = Var<'t>;
+    fn div(self, rhs: Scalar<f64, Dimensionless>) -> Var<'t> {
+        self.unary(self.value / rhs.raw(), 1.0 / rhs.raw())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // d/dx of `f` at `x` from one backward pass
+    fn derivative(f: impl for<'t> Fn(Var<'t>) -> Var<'t>, x: f64) -> f64 {
+        let tape = Tape::new();
+        let v = tape.var(dless!(x));
+        f(v).backward().wrt(v).raw()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn elementary_functions() {
+        let x: f64 = 0.7;
+        assert!(close(derivative(|v| v.exp(), x), x.exp()));
+        assert!(close(derivative(|v| v.ln(), x), 1.0 / x));
+        assert!(close(derivative(|v| v.tanh(), x), 1.0 / x.cosh().powi(2)));
+        assert!(close(derivative(|v| v.sqrt(), x), 0.5 / x.sqrt()));
+        assert!(close(derivative(|v| v.powi(3), x), 3.0 * x * x));
+        assert_eq!(derivative(|v| (-v).mag(), x), 1.0);
+    }
+
+    #[test]
+    fn chain_and_product_rules() {
+        let x: f64 = 1.3;
+        // d/dx ln(1 + e^{x²}) = 2x·e^{x²} / (1 + e^{x²})
+        let s = (x * x).exp();
+        assert!(close(derivative(|v| ((v * v).exp() + dless!(1.0)).ln(), x), 2.0 * x * s / (1.0 + s)));
+        // d/dx x·tanh(x) / (x + 2) by the quotient rule
+        let (t, q) = (x.tanh(), x + 2.0);
+        let want = ((t + x * (1.0 - t * t)) * q - x * t) / (q * q);
+        assert!(close(derivative(|v| v * v.tanh() / (v + dless!(2.0)), x), want));
+    }
+
+    #[test]
+    fn reused_values_accumulate() {
+        let tape = Tape::new();
+        let c = tape.vector(&crate::from_array(std::array::from_fn(|k| k as f64)));
+        // |c|² through the norm, ∂/∂c_k = 2c_k
+        let n = c.norm();
+        let g = crate::to_array(&(n * n).backward().wrt_vector(&c));
+        assert!(g.iter().enumerate().all(|(k, &v)| close(v, 2.0 * k as f64)), "{:?}", g);
+    }
+}
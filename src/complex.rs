@@ -0,0 +1,97 @@
+use slut::complex::c64;
+
+use crate::loss::Real;
+use crate::{N, Params};
+
+/// The parameter vector lifted into complex numbers.
+///
+/// For real-analytic `f`, `Im f(c + i·h·e_k) / h` is `∂f/∂c_k` up to
+/// `O(h²)` with no subtraction, so `h` can be taken as small as `1e-20`
+/// and the result is exact to machine precision.
+pub type ComplexParams = [c64; N];
+
+// e^(a + bi) = e^a (cos b + i sin b)
+fn exp(z: c64) -> c64 {
+    let r = z.re().exp();
+    c64::new(r * z.im().cos(), r * z.im().sin())
+}
+
+// Complex extensions of the real functions, so the imaginary part carries
+// the derivative. `abs` is `±z`, analytic away from zero.
+impl Real for c64 {
+    fn raw(&self) -> f64 {
+        self.re()
+    }
+
+    fn lift(&self, v: f64) -> Self {
+        c64::from(v)
+    }
+
+    fn exp(self) -> Self {
+        exp(self)
+    }
+
+    fn ln(self) -> Self {
+        c64::new(self.mag().ln(), self.arg())
+    }
+
+    fn tanh(self) -> Self {
+        // in terms of e^(-2|z|) so large residuals do not overflow
+        if self.re() < 0.0 {
+            return -(-self).tanh();
+        }
+        let e = exp(self * -2.0);
+        (1.0 - e) / (1.0 + e)
+    }
+
+    fn sqrt(self) -> Self {
+        c64::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        if self.re() < 0.0 { -self } else { self }
+    }
+}
+
+/// The coefficients with `h` added to the imaginary part of coefficient `k`.
+pub fn perturb(c: &Params, k: usize, h: f64) -> ComplexParams {
+    std::array::from_fn(|j| c64::new(c.get_at(0, j, 0).raw(), if j == k { h } else { 0.0 }))
+}
+
+/// Complex equivalent of `dot!(coeffs, inputs)` for real inputs.
+pub fn dot(coeffs: &ComplexParams, inputs: &Params) -> c64 {
+    coeffs.iter().enumerate().map(|(k, &c)| c * inputs.get_at(0, k, 0).raw()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Fun = fn(c64) -> c64;
+
+    // Im f(x + ih)/h against f'(x)
+    fn derivative(f: Fun, x: f64) -> f64 {
+        f(c64::new(x, 1e-20)).im() / 1e-20
+    }
+
+    #[test]
+    fn elementary_functions_carry_their_derivatives() {
+        for x in [-2.5, -0.3, 0.4, 1.7] {
+            let t = x.tanh();
+            let cases: [(&str, Fun, f64); 4] = [
+                ("exp", Real::exp, x.exp()),
+                ("tanh", Real::tanh, 1.0 - t * t),
+                ("abs", Real::abs, x.signum()),
+                ("square", |z| z * z, 2.0 * x),
+            ];
+            for (name, f, want) in cases {
+                let got = derivative(f, x);
+                assert!((got - want).abs() <= 1e-15 * want.abs().max(1.0), "{} at {}: {} vs {}", name, x, got, want);
+            }
+        }
+        for x in [0.2, 3.0] {
+            assert!((derivative(Real::ln, x) - 1.0 / x).abs() < 1e-15);
+            assert!((derivative(Real::sqrt, x) - 0.5 / x.sqrt()).abs() < 1e-15);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use crate::data::Dataset;
+use crate::dims::{parse_unit, Dim, UnitSpec};
+
+/// Which CSV column to read, by position or by header name. A name matches
+/// the header with or without its `[unit]`.
+#[derive(Clone, Debug)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+/// What to do with empty, `NA`, `NaN` or `null` fields.
+#[derive(Clone, Copy, Debug)]
+pub enum Missing {
+    /// Drop the whole row.
+    Skip,
+    /// Fail the load.
+    Error,
+    /// Use this value instead.
+    Fill(f64),
+}
+
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// `None` to detect it: the first row is a header if any of its fields
+    /// is not a number.
+    pub has_header: Option<bool>,
+    pub x: Column,
+    pub y: Column,
+    pub missing: Missing,
+    /// Dimensions the `x` and `y` columns must have, any when `None`.
+    pub x_dim: Option<Dim>,
+    pub y_dim: Option<Dim>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            has_header: None,
+            x: Column::Index(0),
+            y: Column::Index(1),
+            missing: Missing::Skip,
+            x_dim: None,
+            y_dim: None,
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn is_missing(field: &str) -> bool {
+    matches!(field, "" | "NA" | "N/A" | "NaN" | "nan" | "null" | "NULL")
+}
+
+// strip surrounding quotes and whitespace
+fn clean(field: &str) -> &str {
+    field.trim().trim_matches('"').trim()
+}
+
+/// A header, if there is one, and data rows split into fields.
+pub type Rows = (Option<Vec<String>>, Vec<Vec<String>>);
+
+/// Header of a CSV file, if it has one, and its data rows split into fields.
+pub fn read_rows(path: &str, delimiter: char, has_header: Option<bool>) -> io::Result<Rows> {
+    let text = fs::read_to_string(path)?;
+    let mut rows: Vec<Vec<String>> = text
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(|l| l.split(delimiter).map(|f| clean(f).to_string()).collect())
+        .collect();
+
+    let header = match has_header {
+        Some(h) => h,
+        None => rows
+            .first()
+            .is_some_and(|r| r.iter().any(|f| !is_missing(f) && f.parse::<f64>().is_err())),
+    };
+    let header = if header && !rows.is_empty() { Some(rows.remove(0)) } else { None };
+    Ok((header, rows))
+}
+
+/// Split a header like `time[ms]` into its name and unit.
+pub fn split_header(field: &str) -> (&str, Option<&str>) {
+    match field.strip_suffix(']').and_then(|f| f.split_once('[')) {
+        Some((name, unit)) => (name.trim(), Some(unit.trim())),
+        None => (field, None),
+    }
+}
+
+fn resolve(col: &Column, header: &Option<Vec<String>>, path: &str) -> io::Result<usize> {
+    match col {
+        Column::Index(i) => Ok(*i),
+        Column::Name(name) => header
+            .as_ref()
+            .and_then(|h| h.iter().position(|f| f == name || split_header(f).0 == name))
+            .ok_or_else(|| invalid(format!("{}: no column named {}", path, name))),
+    }
+}
+
+// Unit of column `i` from its header, dimensionless without one, checked
+// against the expected dimension
+fn column_unit(header: &Option<Vec<String>>, i: usize, expected: Option<Dim>, path: &str) -> io::Result<UnitSpec> {
+    let field = header.as_ref().and_then(|h| h.get(i)).map(|s| s.as_str()).unwrap_or("");
+    let unit = match split_header(field).1 {
+        Some(u) => parse_unit(u).map_err(|e| invalid(format!("{}: column {}: {}", path, field, e)))?,
+        None => UnitSpec { scale: 1.0, offset: 0.0, dim: Dim::NONE },
+    };
+    if let Some(d) = expected {
+        unit.dim
+            .expect(d, &format!("column {}", field))
+            .map_err(|e| invalid(format!("{}: {}", path, e)))?;
+    }
+    Ok(unit)
+}
+
+/// Load a dataset from the `x` and `y` columns of a CSV file.
+///
+/// Headers like `time[ms]` or `force[kN]` give the unit of a column. Its
+/// values, fill values included, are converted to canonical SI units and
+/// its dimension is attached to the dataset.
+pub fn read_csv(path: &str, opts: &CsvOptions) -> io::Result<Dataset> {
+    let (header, rows) = read_rows(path, opts.delimiter, opts.has_header)?;
+    let xi = resolve(&opts.x, &header, path)?;
+    let yi = resolve(&opts.y, &header, path)?;
+    // width from the header, or the first row without one
+    if let Some(width) = header.as_ref().or(rows.first()).map(|r| r.len()) {
+        for i in [xi, yi] {
+            if i >= width {
+                return Err(invalid(format!("{}: column {} out of range, the file has {} columns", path, i, width)));
+            }
+        }
+    }
+    let xu = column_unit(&header, xi, opts.x_dim, path)?;
+    let yu = column_unit(&header, yi, opts.y_dim, path)?;
+
+    let mut data = Dataset::default().with_dims(xu.dim, yu.dim);
+    'rows: for (n, row) in rows.iter().enumerate() {
+        let mut vals = [0.0f64; 2];
+        for (v, &i) in vals.iter_mut().zip(&[xi, yi]) {
+            let field = row.get(i).map(|s| s.as_str()).unwrap_or("");
+            if is_missing(field) {
+                match opts.missing {
+                    Missing::Skip => continue 'rows,
+                    Missing::Error => {
+                        return Err(invalid(format!("{}: row {}: missing value in column {}", path, n + 1, i)));
+                    }
+                    Missing::Fill(f) => *v = f,
+                }
+            } else {
+                *v = field
+                    .parse()
+                    .map_err(|e| invalid(format!("{}: row {}: column {}: {}", path, n + 1, i, e)))?;
+            }
+        }
+        data.x.push(xu.to_si(vals[0]));
+        data.y.push(yu.to_si(vals[1]));
+    }
+    Ok(data)
+}
+
+/// Write equally long columns under the given header.
+pub fn write_csv(path: &str, header: &[&str], columns: &[&[f64]]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "{}", header.join(","))?;
+
+    let rows = columns.iter().map(|c| c.len()).min().unwrap_or(0);
+    for i in 0..rows {
+        let line: Vec<String> = columns.iter().map(|c| c[i].to_string()).collect();
+        writeln!(out, "{}", line.join(","))?;
+    }
+    out.flush()
+}
+
+/// Dump `x, y, prediction, residual` for every sample, headers annotated
+/// with the SI unit of dimensioned columns so the file reads back as is.
+pub fn write_predictions<F>(path: &str, data: &Dataset, predict: F) -> io::Result<()>
+where
+    F: Fn(f64) -> f64,
+{
+    let pred: Vec<f64> = data.x.iter().map(|&x| predict(x)).collect();
+    let resid: Vec<f64> = pred.iter().zip(&data.y).map(|(p, y)| p - y).collect();
+    let col = |name: &str, d: Dim| if d.is_none() { name.to_string() } else { format!("{}[{}]", name, d) };
+    let header = [col("x", data.x_dim), col("y", data.y_dim), col("prediction", data.y_dim), col("residual", data.y_dim)];
+    let header: Vec<&str> = header.iter().map(|h| h.as_str()).collect();
+    write_csv(path, &header, &[&data.x, &data.y, &pred, &resid])
+}
+
+#[cfg(test)]
+mod tests {
+    use slut::dimension::{Length, Time};
+    use slut::si::Force;
+
+    use super::*;
+    use crate::dims::Dimensional;
+
+    // write `text` to a temporary file private to this test run and return its path
+    fn file(name: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!("slut-ml-csv-{}-{}.csv", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn converts_header_units_to_si() {
+        let path = file("units", "time[ms],force[lbf]\n1000,1\n2500,2\n");
+        let d = read_csv(&path, &CsvOptions::default()).unwrap();
+        assert_eq!(d.x, [1.0, 2.5]);
+        assert_eq!(d.y, [4.44822, 8.89644]);
+        assert_eq!(d.x_dim, Time::DIM);
+        assert_eq!(d.y_dim, Force::DIM);
+    }
+
+    #[test]
+    fn detects_a_missing_header() {
+        let path = file("noheader", "# comment\n1,2\n\n3,4\n");
+        let d = read_csv(&path, &CsvOptions::default()).unwrap();
+        assert_eq!((d.x, d.y), (vec![1.0, 3.0], vec![2.0, 4.0]));
+        assert!(d.y_dim.is_none());
+    }
+
+    #[test]
+    fn selects_columns_by_name() {
+        let path = file("names", "a;h[cm];t[s]\n9;150;1\n9;\"250\";2\n");
+        let opts = CsvOptions { delimiter: ';', x: Column::Name("t".into()), y: Column::Name("h".into()), ..Default::default() };
+        let d = read_csv(&path, &opts).unwrap();
+        assert_eq!((d.x, d.y), (vec![1.0, 2.0], vec![1.5, 2.5]));
+        let opts = CsvOptions { x: Column::Name("nope".into()), ..opts };
+        assert!(read_csv(&path, &opts).is_err());
+    }
+
+    #[test]
+    fn handles_missing_values() {
+        let path = file("missing", "x,y\n1,NA\n2,4\n,6\n");
+        let skip = read_csv(&path, &CsvOptions::default()).unwrap();
+        assert_eq!((skip.x, skip.y), (vec![2.0], vec![4.0]));
+        let fill = read_csv(&path, &CsvOptions { missing: Missing::Fill(0.0), ..Default::default() }).unwrap();
+        assert_eq!((fill.x, fill.y), (vec![1.0, 2.0, 0.0], vec![0.0, 4.0, 6.0]));
+        assert!(read_csv(&path, &CsvOptions { missing: Missing::Error, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn rejects_an_index_past_the_last_column() {
+        let path = file("range", "1,2\n3,4\n");
+        let err = read_csv(&path, &CsvOptions { y: Column::Index(2), ..Default::default() }).unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{}", err);
+        let path = file("range-header", "x,y\n1,2\n");
+        assert!(read_csv(&path, &CsvOptions { x: Column::Index(5), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn rejects_an_unexpected_dimension() {
+        let path = file("dims", "t[s],h[m]\n1,2\n");
+        let opts = CsvOptions { y_dim: Some(Time::DIM), ..Default::default() };
+        assert!(read_csv(&path, &opts).is_err());
+    }
+
+    #[test]
+    fn predictions_read_back() {
+        let data = Dataset::new(vec![1.0, 2.0], vec![3.0, 5.0]).with_dims(Time::DIM, Length::DIM);
+        let path = file("predictions", "");
+        write_predictions(&path, &data, |x| 2.0 * x).unwrap();
+        let back = read_csv(&path, &CsvOptions { y: Column::Name("residual".into()), ..Default::default() }).unwrap();
+        assert_eq!(back.x, data.x);
+        assert_eq!(back.y, [-1.0, -1.0]);
+        assert_eq!((back.x_dim, back.y_dim), (Time::DIM, Length::DIM));
+    }
+}
@@ -0,0 +1,214 @@
+use slut::dimension::Dimensionless;
+use slut::tensor::*;
+
+use crate::dims::Dimensional;
+use crate::N;
+
+/// When to unlock the next polynomial term. Loss thresholds are typed by
+/// the slut dimension `L` of the loss.
+#[derive(Clone, Debug)]
+pub enum Policy<L: Dimensional = Dimensionless> {
+    /// Every term is trainable from the start.
+    Off,
+    /// The original rule: checked every `every` epochs, unlock once the loss
+    /// change rises above `threshold` (i.e. the descent has stalled), at most
+    /// once per `cooldown` epochs and never before epoch `first`.
+    Plateau { threshold: Scalar<f64, L>, every: usize, cooldown: usize, first: usize },
+    /// Unlock a term every `every` epochs.
+    Epochs { every: usize },
+    /// Unlock when the validation loss has not improved by `min_delta` for
+    /// `patience` epochs.
+    Validation { patience: usize, min_delta: Scalar<f64, L> },
+}
+
+/// A decision taken by the curriculum.
+#[derive(Clone, Debug)]
+pub struct Unlock<L: Dimensional = Dimensionless> {
+    pub epoch: usize,
+    pub terms: usize,
+    pub loss: Scalar<f64, L>,
+    pub reason: &'static str,
+}
+
+/// Progressive-degree curriculum: only the first `enabled` coefficients are
+/// trained, the others stay frozen until the policy unlocks them.
+///
+/// Each trainer owns its own curriculum, so independent trainings never
+/// share state.
+#[derive(Clone, Debug)]
+pub struct Curriculum<L: Dimensional = Dimensionless> {
+    pub policy: Policy<L>,
+    pub max_terms: usize,
+    pub events: Vec<Unlock<L>>,
+    enabled: usize,
+    last_unlock: Option<usize>,
+    best_val: Scalar<f64, L>,
+    bad_epochs: usize,
+}
+
+impl<L: Dimensional> Curriculum<L> {
+    pub fn new(policy: Policy<L>, start: usize) -> Self {
+        let enabled = match policy {
+            Policy::Off => N,
+            _ => start.min(N),
+        };
+        Curriculum {
+            policy,
+            max_terms: N,
+            events: Vec::new(),
+            enabled,
+            last_unlock: None,
+            best_val: Scalar::default([f64::INFINITY]),
+            bad_epochs: 0,
+        }
+    }
+
+    /// Train every term from the first epoch.
+    pub fn off() -> Self {
+        Curriculum::new(Policy::Off, N)
+    }
+
+    /// The rule the training loop used to apply: start with 3 terms.
+    pub fn plateau(threshold: Scalar<f64, L>) -> Self {
+        Curriculum::new(Policy::Plateau { threshold, every: 5, cooldown: 500, first: 200 }, 3)
+    }
+
+    pub fn epochs(every: usize) -> Self {
+        Curriculum::new(Policy::Epochs { every }, 3)
+    }
+
+    pub fn validation(patience: usize, min_delta: Scalar<f64, L>) -> Self {
+        Curriculum::new(Policy::Validation { patience, min_delta }, 3)
+    }
+
+    /// Look up a curriculum with its default settings.
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Curriculum::off()),
+            "plateau" => Some(Curriculum::plateau(Scalar::default([-5e-5]))),
+            "epochs" => Some(Curriculum::epochs(500)),
+            "validation" => Some(Curriculum::validation(100, Scalar::default([1e-7]))),
+            _ => None,
+        }
+    }
+
+    /// Number of coefficients currently trainable.
+    pub fn enabled(&self) -> usize {
+        self.enabled
+    }
+
+    /// Convergence threshold of the plateau policy, if that is the policy.
+    pub fn threshold(&self) -> Option<Scalar<f64, L>> {
+        match self.policy {
+            Policy::Plateau { threshold, .. } => Some(threshold),
+            _ => None,
+        }
+    }
+
+    pub fn needs_validation(&self) -> bool {
+        matches!(self.policy, Policy::Validation { .. })
+    }
+
+    /// Feed the result of one epoch. `dl` is the change in training loss
+    /// since the previous epoch. Returns the unlock decision, if any.
+    pub fn update(
+        &mut self,
+        epoch: usize,
+        loss: Scalar<f64, L>,
+        dl: Scalar<f64, L>,
+        val_loss: Option<Scalar<f64, L>>,
+    ) -> Option<Unlock<L>> {
+        if self.enabled >= self.max_terms {
+            return None;
+        }
+
+        let reason = match &self.policy {
+            Policy::Off => None,
+            Policy::Plateau { threshold, every, cooldown, first } => {
+                let cooled = match self.last_unlock {
+                    Some(last) => epoch - last > *cooldown,
+                    None => epoch > *first,
+                };
+                if epoch.is_multiple_of(*every) && dl > *threshold && cooled {
+                    Some("loss plateau")
+                } else {
+                    None
+                }
+            }
+            Policy::Epochs { every } => {
+                if epoch > 0 && epoch.is_multiple_of(*every) { Some("epoch schedule") } else { None }
+            }
+            Policy::Validation { patience, min_delta } => match val_loss {
+                Some(v) if v < self.best_val - *min_delta => {
+                    self.best_val = v;
+                    self.bad_epochs = 0;
+                    None
+                }
+                Some(_) => {
+                    self.bad_epochs += 1;
+                    if self.bad_epochs > *patience { Some("validation plateau") } else { None }
+                }
+                None => None,
+            },
+        };
+
+        let reason = reason?;
+        self.enabled += 1;
+        self.last_unlock = Some(epoch);
+        self.bad_epochs = 0;
+        self.best_val = Scalar::default([f64::INFINITY]);
+
+        let u = Unlock { epoch, terms: self.enabled, loss, reason };
+        self.events.push(u.clone());
+        Some(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zero() -> Scalar<f64, Dimensionless> {
+        Scalar::zero()
+    }
+
+    #[test]
+    fn epoch_schedule_unlocks_up_to_the_cap() {
+        let mut c: Curriculum = Curriculum::epochs(2);
+        c.max_terms = 5;
+        let unlocked: Vec<usize> = (0..20).filter(|&e| c.update(e, zero(), zero(), None).is_some()).collect();
+        assert_eq!(unlocked, [2, 4]);
+        assert_eq!(c.enabled(), 5);
+        assert_eq!(c.events.iter().map(|u| u.terms).collect::<Vec<_>>(), [4, 5]);
+    }
+
+    #[test]
+    fn plateau_waits_for_the_first_epoch_and_cooldown() {
+        let mut c: Curriculum = Curriculum::new(Policy::Plateau { threshold: Scalar::default([-1.0]), every: 1, cooldown: 3, first: 2 }, 3);
+        // the loss barely moves, so every epoch counts as stalled
+        let unlocked: Vec<usize> = (0..10).filter(|&e| c.update(e, zero(), zero(), None).is_some()).collect();
+        assert_eq!(unlocked, [3, 7]);
+    }
+
+    #[test]
+    fn validation_patience() {
+        let mut c: Curriculum = Curriculum::validation(2, Scalar::zero());
+        let val = [3.0, 2.0, 2.5, 2.5, 2.5, 1.0];
+        let unlocked: Vec<usize> = val
+            .iter()
+            .enumerate()
+            .filter(|&(e, &v)| c.update(e, zero(), zero(), Some(Scalar::default([v]))).is_some())
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(unlocked, [4]);
+        assert!(c.needs_validation());
+    }
+
+    #[test]
+    fn off_trains_everything() {
+        let mut c: Curriculum = Curriculum::off();
+        assert_eq!(c.enabled(), N);
+        assert!(c.update(1000, zero(), zero(), None).is_none());
+        assert!(Curriculum::<Dimensionless>::by_name("sometimes").is_none());
+    }
+}
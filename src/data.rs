@@ -0,0 +1,321 @@
+use std::fs;
+use std::io;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use slut::tensor::*;
+
+use crate::dims::{Dim, DimError, Dimensional};
+
+/// How `Dataset::split` assigns samples to the subsets.
+#[derive(Clone, Copy, Debug)]
+pub enum Split {
+    /// Seeded random assignment.
+    Random { seed: u64 },
+    /// Deterministic, evenly spread along the sample order.
+    Interleaved,
+}
+
+/// A set of `(x, y)` training samples.
+///
+/// Values are stored in canonical SI units, with the dimensions of `x` and
+/// `y` alongside (dimensionless unless set).
+#[derive(Clone, Debug, Default)]
+pub struct Dataset {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub x_dim: Dim,
+    pub y_dim: Dim,
+}
+
+impl Dataset {
+    pub fn new(x: Vec<f64>, y: Vec<f64>) -> Self {
+        assert_eq!(x.len(), y.len(), "x and y must have the same number of samples");
+        Dataset { x, y, ..Default::default() }
+    }
+
+    /// Samples from slut scalars, the dimensions taken from their types.
+    pub fn from_scalars<X: Dimensional, Y: Dimensional>(x: &[Scalar<f64, X>], y: &[Scalar<f64, Y>]) -> Self {
+        Dataset::new(x.iter().map(|s| s.raw()).collect(), y.iter().map(|s| s.raw()).collect())
+            .with_dims(X::DIM, Y::DIM)
+    }
+
+    pub fn with_dims(mut self, x_dim: Dim, y_dim: Dim) -> Self {
+        self.x_dim = x_dim;
+        self.y_dim = y_dim;
+        self
+    }
+
+    /// `Err` unless `other` has the same input and target dimensions.
+    pub fn check_dims(&self, other: &Dataset, what: &str) -> Result<(), DimError> {
+        other.x_dim.expect(self.x_dim, &format!("{} inputs", what))?;
+        other.y_dim.expect(self.y_dim, &format!("{} targets", what))
+    }
+
+    /// `count` samples of `f` at `start + i·step`.
+    pub fn grid<F>(f: F, start: f64, step: f64, count: usize) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        let x: Vec<f64> = (0..count).map(|i| start + i as f64 * step).collect();
+        let y = x.iter().map(|&x| f(x)).collect();
+        Dataset { x, y, ..Default::default() }
+    }
+
+    /// `count` samples of `f` at uniformly random points of `[min, max)`,
+    /// sorted by `x`.
+    pub fn sample<F>(f: F, min: f64, max: f64, count: usize, seed: u64) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut x: Vec<f64> = (0..count).map(|_| rng.random_range(min..max)).collect();
+        x.sort_by(f64::total_cmp);
+        let y = x.iter().map(|&x| f(x)).collect();
+        Dataset { x, y, ..Default::default() }
+    }
+
+    /// Read two whitespace or comma separated columns `x y` per line.
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut data = Dataset::default();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let cols: Vec<&str> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .collect();
+            let parse = |s: &str| {
+                s.parse::<f64>().map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", path, n + 1, e))
+                })
+            };
+            if cols.len() < 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: expected two columns", path, n + 1),
+                ));
+            }
+            data.x.push(parse(cols[0])?);
+            data.y.push(parse(cols[1])?);
+        }
+        Ok(data)
+    }
+
+    /// Load by extension: `.npy` (columns 0 and 1), `.csv` (default
+    /// `CsvOptions`), anything else through `from_file`.
+    pub fn load(path: &str) -> io::Result<Self> {
+        if path.ends_with(".npy") {
+            crate::npy::read_dataset(path, 0, 1)
+        } else if path.ends_with(".csv") {
+            crate::csv::read_csv(path, &crate::csv::CsvOptions::default())
+        } else {
+            Dataset::from_file(path)
+        }
+    }
+
+    /// Samples at the given indices, in that order.
+    pub fn subset(&self, idx: &[usize]) -> Self {
+        Dataset {
+            x: idx.iter().map(|&i| self.x[i]).collect(),
+            y: idx.iter().map(|&i| self.y[i]).collect(),
+            x_dim: self.x_dim,
+            y_dim: self.y_dim,
+        }
+    }
+
+    /// Split into `(train, validation, test)` with the given fractions for
+    /// the last two. Each subset keeps the original sample order.
+    ///
+    /// Panics unless both fractions are non-negative and sum to at most 1.
+    pub fn split(&self, val: f64, test: f64, how: Split) -> (Dataset, Dataset, Dataset) {
+        assert!(
+            val >= 0.0 && test >= 0.0 && val + test <= 1.0,
+            "split fractions must be non-negative and sum to at most 1, got validation {} and test {}",
+            val, test
+        );
+        let n = self.len();
+        let mut train = Vec::new();
+        let mut v = Vec::new();
+        let mut t = Vec::new();
+
+        match how {
+            Split::Random { seed } => {
+                let mut idx: Vec<usize> = (0..n).collect();
+                idx.shuffle(&mut StdRng::seed_from_u64(seed));
+                // rounding both up must not take more than all samples
+                let n_test = (n as f64 * test).round() as usize;
+                let n_val = ((n as f64 * val).round() as usize).min(n - n_test);
+                t.extend_from_slice(&idx[..n_test]);
+                v.extend_from_slice(&idx[n_test..n_test + n_val]);
+                train.extend_from_slice(&idx[n_test + n_val..]);
+                t.sort_unstable();
+                v.sort_unstable();
+                train.sort_unstable();
+            }
+            Split::Interleaved => {
+                // accumulate the fractions and emit a sample whenever one
+                // reaches a whole count
+                let (mut acc_v, mut acc_t) = (0.0, 0.0);
+                for i in 0..n {
+                    acc_v += val;
+                    acc_t += test;
+                    if acc_t >= 1.0 {
+                        acc_t -= 1.0;
+                        t.push(i);
+                    } else if acc_v >= 1.0 {
+                        acc_v -= 1.0;
+                        v.push(i);
+                    } else {
+                        train.push(i);
+                    }
+                }
+            }
+        }
+        (self.subset(&train), self.subset(&v), self.subset(&t))
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.x.iter().copied().zip(self.y.iter().copied())
+    }
+
+    /// Smallest and largest `x`.
+    pub fn range(&self) -> (f64, f64) {
+        self.x.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x)))
+    }
+}
+
+/// Splits a dataset into mini-batches, reshuffling every epoch with its own
+/// seeded generator so runs are reproducible.
+pub struct Batcher {
+    pub batch_size: usize,
+    pub shuffle: bool,
+    /// Skip the last batch when it is smaller than `batch_size`.
+    pub drop_last: bool,
+    rng: StdRng,
+}
+
+impl Batcher {
+    pub fn new(batch_size: usize, seed: u64) -> Self {
+        Batcher {
+            batch_size: batch_size.max(1),
+            shuffle: true,
+            drop_last: false,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Number of batches `batches` yields for `n` samples.
+    pub fn steps_per_epoch(&self, n: usize) -> usize {
+        if self.drop_last { n / self.batch_size } else { n.div_ceil(self.batch_size) }
+    }
+
+    /// The batches for one epoch.
+    pub fn batches(&mut self, data: &Dataset) -> Vec<Dataset> {
+        let mut idx: Vec<usize> = (0..data.len()).collect();
+        if self.shuffle {
+            idx.shuffle(&mut self.rng);
+        }
+        idx.chunks(self.batch_size)
+            .filter(|c| !self.drop_last || c.len() == self.batch_size)
+            .map(|c| data.subset(c))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(n: usize) -> Dataset {
+        Dataset::grid(|x| 2.0 * x, 0.0, 1.0, n)
+    }
+
+    // every sample lands in exactly one subset, in order
+    fn assert_partition(d: &Dataset, parts: [&Dataset; 3]) {
+        let mut xs: Vec<f64> = parts.iter().flat_map(|p| p.x.clone()).collect();
+        xs.sort_by(f64::total_cmp);
+        assert_eq!(xs, d.x);
+        for p in parts {
+            assert!(p.x.windows(2).all(|w| w[0] < w[1]));
+            assert!(p.iter().all(|(x, y)| y == 2.0 * x));
+        }
+    }
+
+    #[test]
+    fn random_split_sizes() {
+        let d = data(100);
+        let (train, val, test) = d.split(0.2, 0.1, Split::Random { seed: 1 });
+        assert_eq!((train.len(), val.len(), test.len()), (70, 20, 10));
+        assert_partition(&d, [&train, &val, &test]);
+        // reproducible
+        assert_eq!(d.split(0.2, 0.1, Split::Random { seed: 1 }).1.x, val.x);
+    }
+
+    #[test]
+    fn interleaved_split_spreads_samples() {
+        let d = data(20);
+        let (train, val, test) = d.split(0.25, 0.25, Split::Interleaved);
+        assert_eq!(test.len(), 5);
+        assert!((4..=5).contains(&val.len()));
+        assert!(test.x.windows(2).all(|w| w[1] - w[0] == 4.0));
+        assert_partition(&d, [&train, &val, &test]);
+    }
+
+    #[test]
+    fn whole_dataset_split_rounds_within_bounds() {
+        let d = data(3);
+        let (train, val, test) = d.split(0.5, 0.5, Split::Random { seed: 0 });
+        assert_eq!(train.len() + val.len() + test.len(), 3);
+        assert!(train.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "sum to at most 1")]
+    fn split_rejects_fractions_over_one() {
+        data(10).split(0.6, 0.6, Split::Interleaved);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative")]
+    fn split_rejects_negative_fractions() {
+        data(10).split(-0.1, 0.2, Split::Interleaved);
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN")]
+    fn split_rejects_nan() {
+        data(10).split(f64::NAN, 0.2, Split::Random { seed: 0 });
+    }
+
+    #[test]
+    fn batches_cover_each_epoch() {
+        let d = data(10);
+        let mut b = Batcher::new(4, 3);
+        assert_eq!(b.steps_per_epoch(d.len()), 3);
+        let batches = b.batches(&d);
+        assert_eq!(batches.iter().map(|b| b.len()).collect::<Vec<_>>(), [4, 4, 2]);
+        let mut xs: Vec<f64> = batches.iter().flat_map(|b| b.x.clone()).collect();
+        xs.sort_by(f64::total_cmp);
+        assert_eq!(xs, d.x);
+
+        b.drop_last = true;
+        assert_eq!(b.steps_per_epoch(d.len()), 2);
+        assert_eq!(b.batches(&d).len(), 2);
+    }
+}
@@ -0,0 +1,387 @@
+use std::cmp::Ordering;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::newton::{SolveOptions, SolveReport};
+use crate::{from_array, to_array, N, Params};
+
+// Only the first `n` coefficients move, the rest keep their value from `c0`.
+fn embed(c0: &[f64; N], x: &[f64]) -> Params {
+    let mut c = *c0;
+    c[..x.len()].copy_from_slice(x);
+    from_array(c)
+}
+
+// Objective order with NaN ranked worst, so a point where the objective is
+// undefined never displaces one where it is.
+fn by_value(a: f64, b: f64) -> Ordering {
+    let rank = |v: f64| if v.is_nan() { f64::INFINITY } else { v };
+    rank(a).total_cmp(&rank(b))
+}
+
+fn better(a: f64, b: f64) -> bool {
+    by_value(a, b).is_lt()
+}
+
+// Relative spread of the objective, with an absolute floor of `ftol²` for
+// objectives that go to zero.
+fn converged_by(spread: f64, best: f64, opts: &SolveOptions) -> bool {
+    spread <= opts.ftol * (best.abs() + opts.ftol)
+}
+
+/// Nelder–Mead simplex search over the first `n` coefficients, with the
+/// usual reflection, expansion, contraction and shrink coefficients
+/// (1, 2, ½, ½). Converges when the objective values across the simplex
+/// agree to `ftol`, relative.
+pub fn nelder_mead<F>(f: F, c0: &Params, n: usize, opts: &SolveOptions) -> SolveReport
+where
+    F: Fn(&Params) -> f64,
+{
+    let base = to_array(c0);
+    let eval = |x: &[f64], report: &mut SolveReport| {
+        report.evaluations += 1;
+        f(&embed(&base, x))
+    };
+    let mut report = SolveReport::start(c0, f(c0));
+
+    // initial simplex: c0 and one vertex moved along each axis
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((base[..n].to_vec(), report.loss));
+    for k in 0..n {
+        let mut x = base[..n].to_vec();
+        x[k] += if x[k] == 0.0 { 0.1 } else { 0.05 * x[k] };
+        let v = eval(&x, &mut report);
+        simplex.push((x, v));
+    }
+
+    while report.iterations < opts.max_iter {
+        simplex.sort_by(|a, b| by_value(a.1, b.1));
+        let (best, worst) = (simplex[0].1, simplex[n].1);
+        report.iterations += 1;
+        report.history.push(best);
+        if converged_by(worst - best, best, opts) {
+            let c = to_array(&embed(&base, &simplex[0].0));
+            return report.finish(c, best, true, "simplex values agree");
+        }
+
+        // centroid of all but the worst vertex
+        let centroid: Vec<f64> = (0..n).map(|k| simplex[..n].iter().map(|v| v.0[k]).sum::<f64>() / n as f64).collect();
+        let along = |t: f64| -> Vec<f64> { (0..n).map(|k| centroid[k] + t * (simplex[n].0[k] - centroid[k])).collect() };
+
+        let xr = along(-1.0);
+        let fr = eval(&xr, &mut report);
+        if better(fr, best) {
+            let xe = along(-2.0);
+            let fe = eval(&xe, &mut report);
+            simplex[n] = if better(fe, fr) { (xe, fe) } else { (xr, fr) };
+        } else if better(fr, simplex[n - 1].1) {
+            simplex[n] = (xr, fr);
+        } else {
+            // contract towards the better of the worst and its reflection
+            let (xc, fc) = if better(fr, worst) {
+                let x = along(-0.5);
+                let v = eval(&x, &mut report);
+                (x, v)
+            } else {
+                let x = along(0.5);
+                let v = eval(&x, &mut report);
+                (x, v)
+            };
+            if better(fc, worst) && better(fc, fr) {
+                simplex[n] = (xc, fc);
+            } else {
+                // shrink everything towards the best vertex
+                let x0 = simplex[0].0.clone();
+                for v in simplex.iter_mut().skip(1) {
+                    let x: Vec<f64> = v.0.iter().zip(&x0).map(|(x, b)| b + 0.5 * (x - b)).collect();
+                    v.1 = eval(&x, &mut report);
+                    v.0 = x;
+                }
+            }
+        }
+    }
+
+    simplex.sort_by(|a, b| by_value(a.1, b.1));
+    let c = to_array(&embed(&base, &simplex[0].0));
+    report.finish(c, simplex[0].1, false, "iteration limit")
+}
+
+/// CMA-ES with rank-one and rank-μ covariance updates and cumulative step
+/// size adaptation, default population `4 + ⌊3 ln n⌋`. Starts as an
+/// isotropic Gaussian of width `sigma` around `c0`; converges when the
+/// search distribution has shrunk below `ftol` times the mean's scale.
+pub fn cma_es<F>(f: F, c0: &Params, n: usize, sigma: f64, seed: u64, opts: &SolveOptions) -> SolveReport
+where
+    F: Fn(&Params) -> f64,
+{
+    let base = to_array(c0);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut report = SolveReport::start(c0, f(c0));
+    let nf = n as f64;
+
+    let lambda = 4 + (3.0 * nf.ln()).floor() as usize;
+    let mu = lambda / 2;
+    let mut weights: Vec<f64> = (0..mu).map(|i| (mu as f64 + 0.5).ln() - ((i + 1) as f64).ln()).collect();
+    let wsum: f64 = weights.iter().sum();
+    weights.iter_mut().for_each(|w| *w /= wsum);
+    let mueff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+
+    let cs = (mueff + 2.0) / (nf + mueff + 5.0);
+    let ds = 1.0 + 2.0 * (((mueff - 1.0) / (nf + 1.0)).sqrt() - 1.0).max(0.0) + cs;
+    let cc = (4.0 + mueff / nf) / (nf + 4.0 + 2.0 * mueff / nf);
+    let c1 = 2.0 / ((nf + 1.3).powi(2) + mueff);
+    let cmu = (1.0 - c1).min(2.0 * (mueff - 2.0 + 1.0 / mueff) / ((nf + 2.0).powi(2) + mueff));
+    let chi_n = nf.sqrt() * (1.0 - 1.0 / (4.0 * nf) + 1.0 / (21.0 * nf * nf));
+
+    let mut mean = base[..n].to_vec();
+    let mut sigma = sigma;
+    let mut cov: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    let (mut ps, mut pc) = (vec![0.0; n], vec![0.0; n]);
+    let (mut best_x, mut best_f) = (mean.clone(), report.loss);
+
+    while report.iterations < opts.max_iter {
+        // C = B·diag(d²)·Bᵀ
+        let (eig, b) = jacobi_eigen(&cov);
+        let d: Vec<f64> = eig.iter().map(|e| e.max(1e-300).sqrt()).collect();
+
+        let mut pop: Vec<(Vec<f64>, Vec<f64>, f64)> = (0..lambda)
+            .map(|_| {
+                let z: Vec<f64> = (0..n).map(|_| normal(&mut rng)).collect();
+                let y: Vec<f64> = (0..n).map(|i| (0..n).map(|j| b[i][j] * d[j] * z[j]).sum()).collect();
+                let x: Vec<f64> = (0..n).map(|i| mean[i] + sigma * y[i]).collect();
+                (x, y, 0.0)
+            })
+            .collect();
+        for p in pop.iter_mut() {
+            p.2 = f(&embed(&base, &p.0));
+        }
+        report.evaluations += lambda;
+        pop.sort_by(|a, b| by_value(a.2, b.2));
+        if better(pop[0].2, best_f) {
+            (best_x, best_f) = (pop[0].0.clone(), pop[0].2);
+        }
+        report.iterations += 1;
+        report.history.push(best_f);
+
+        // recombination
+        let yw: Vec<f64> = (0..n).map(|i| (0..mu).map(|k| weights[k] * pop[k].1[i]).sum()).collect();
+        for i in 0..n {
+            mean[i] += sigma * yw[i];
+        }
+
+        // step size path, through C^-1/2 = B·diag(1/d)·Bᵀ
+        let bt_yw: Vec<f64> = (0..n).map(|j| (0..n).map(|i| b[i][j] * yw[i]).sum::<f64>() / d[j]).collect();
+        let inv_sqrt_yw: Vec<f64> = (0..n).map(|i| (0..n).map(|j| b[i][j] * bt_yw[j]).sum()).collect();
+        let a = (cs * (2.0 - cs) * mueff).sqrt();
+        for i in 0..n {
+            ps[i] = (1.0 - cs) * ps[i] + a * inv_sqrt_yw[i];
+        }
+        let ps_norm = ps.iter().map(|v| v * v).sum::<f64>().sqrt();
+        let g = report.iterations as i32;
+        let hs = ps_norm / (1.0 - (1.0 - cs).powi(2 * g)).sqrt() < (1.4 + 2.0 / (nf + 1.0)) * chi_n;
+        let hs = if hs { 1.0 } else { 0.0 };
+
+        // covariance paths and update
+        let a = (cc * (2.0 - cc) * mueff).sqrt();
+        for i in 0..n {
+            pc[i] = (1.0 - cc) * pc[i] + hs * a * yw[i];
+        }
+        let old = (1.0 - c1 - cmu) + c1 * (1.0 - hs) * cc * (2.0 - cc);
+        for i in 0..n {
+            for j in 0..n {
+                let rank_mu: f64 = (0..mu).map(|k| weights[k] * pop[k].1[i] * pop[k].1[j]).sum();
+                cov[i][j] = old * cov[i][j] + c1 * pc[i] * pc[j] + cmu * rank_mu;
+            }
+        }
+        sigma *= ((cs / ds) * (ps_norm / chi_n - 1.0)).exp();
+
+        let scale = mean.iter().fold(1.0f64, |m, v| m.max(v.abs()));
+        let width = sigma * d.iter().cloned().fold(0.0, f64::max);
+        if width < opts.ftol.sqrt() * scale {
+            let c = to_array(&embed(&base, &best_x));
+            return report.finish(c, best_f, true, "search distribution collapsed");
+        }
+    }
+    let c = to_array(&embed(&base, &best_x));
+    report.finish(c, best_f, false, "iteration limit")
+}
+
+/// Particle swarm with the constriction-factor coefficients (0.7298,
+/// 1.49618, 1.49618). Particles start uniformly within `spread` of `c0`;
+/// converges when the swarm's best values agree to `ftol`, relative.
+pub fn pso<F>(f: F, c0: &Params, n: usize, particles: usize, spread: f64, seed: u64, opts: &SolveOptions) -> SolveReport
+where
+    F: Fn(&Params) -> f64,
+{
+    const W: f64 = 0.7298;
+    const C: f64 = 1.49618;
+    let base = to_array(c0);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut report = SolveReport::start(c0, f(c0));
+
+    let mut pos: Vec<Vec<f64>> = (0..particles)
+        .map(|p| (0..n).map(|k| if p == 0 { base[k] } else { base[k] + rng.random_range(-spread..spread) }).collect())
+        .collect();
+    let mut vel: Vec<Vec<f64>> = (0..particles).map(|_| (0..n).map(|_| rng.random_range(-spread..spread) * 0.1).collect()).collect();
+    let mut own: Vec<(Vec<f64>, f64)> = pos.iter().map(|x| (x.clone(), f(&embed(&base, x)))).collect();
+    report.evaluations += particles;
+    let mut best = own.iter().min_by(|a, b| by_value(a.1, b.1)).unwrap().clone();
+
+    while report.iterations < opts.max_iter {
+        for p in 0..particles {
+            for k in 0..n {
+                let (r1, r2): (f64, f64) = (rng.random(), rng.random());
+                vel[p][k] = W * vel[p][k] + C * r1 * (own[p].0[k] - pos[p][k]) + C * r2 * (best.0[k] - pos[p][k]);
+                pos[p][k] += vel[p][k];
+            }
+            let v = f(&embed(&base, &pos[p]));
+            if better(v, own[p].1) {
+                own[p] = (pos[p].clone(), v);
+                if better(v, best.1) {
+                    best = own[p].clone();
+                }
+            }
+        }
+        report.evaluations += particles;
+        report.iterations += 1;
+        report.history.push(best.1);
+
+        let worst = own.iter().map(|o| o.1).max_by(|a, b| by_value(*a, *b)).unwrap();
+        if converged_by(worst - best.1, best.1, opts) {
+            return report.finish(to_array(&embed(&base, &best.0)), best.1, true, "swarm values agree");
+        }
+    }
+    report.finish(to_array(&embed(&base, &best.0)), best.1, false, "iteration limit")
+}
+
+// Standard normal sample by Box–Muller.
+fn normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = 1.0 - rng.random::<f64>();
+    let u2: f64 = rng.random();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+// Eigenvalues and eigenvectors (as columns) of a symmetric matrix by cyclic
+// Jacobi rotations.
+fn jacobi_eigen(a: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = a.len();
+    let mut a = a.to_vec();
+    let mut v: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+
+    for _sweep in 0..50 {
+        let off: f64 = (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j))).map(|(i, j)| a[i][j] * a[i][j]).sum();
+        if off < 1e-30 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (apk, aqk) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    (*apk, *aqk) = (c * *apk - s * *aqk, s * *apk + c * *aqk);
+                }
+                for row in v.iter_mut() {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = c * vp - s * vq;
+                    row[q] = s * vp + c * vq;
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // bowl around (1, -½), undefined left of the c₀ = 0 wall
+    fn walled(c: &Params) -> f64 {
+        let c = to_array(c);
+        if c[0] < 0.0 { f64::NAN } else { (c[0] - 1.0).powi(2) + 10.0 * (c[1] + 0.5).powi(2) }
+    }
+
+    fn start(c0: f64) -> Params {
+        let mut c = [7.0; N];
+        c[..2].copy_from_slice(&[c0, 0.0]);
+        from_array(c)
+    }
+
+    fn assert_min(report: &SolveReport) {
+        let c = to_array(&report.coeffs);
+        assert!(report.converged, "{}", report.reason);
+        assert!((c[0] - 1.0).abs() < 1e-3 && (c[1] + 0.5).abs() < 1e-3, "{:?} ({})", &c[..2], report.reason);
+        assert!(report.history.iter().skip(1).all(|l| !l.is_nan()));
+        // only the first n coefficients move
+        assert!(c[2..].iter().all(|&v| v == 7.0));
+    }
+
+    #[test]
+    fn nan_ranks_worst() {
+        assert!(better(1e300, f64::NAN));
+        assert!(!better(f64::NAN, f64::INFINITY));
+        let mut v = [f64::NAN, 2.0, -1.0];
+        v.sort_by(|a, b| by_value(*a, *b));
+        assert_eq!(&v[..2], &[-1.0, 2.0]);
+    }
+
+    #[test]
+    fn nelder_mead_steps_back_from_nan() {
+        let opts = SolveOptions { max_iter: 2000, ftol: 1e-14, ..Default::default() };
+        assert_min(&nelder_mead(walled, &start(0.05), 2, &opts));
+    }
+
+    #[test]
+    fn cma_es_starts_from_nan() {
+        let opts = SolveOptions { max_iter: 2000, ftol: 1e-14, ..Default::default() };
+        assert_min(&cma_es(walled, &start(-0.2), 2, 0.5, 3, &opts));
+    }
+
+    #[test]
+    fn pso_starts_from_nan() {
+        let opts = SolveOptions { max_iter: 2000, ftol: 1e-14, ..Default::default() };
+        assert_min(&pso(walled, &start(-0.2), 2, 30, 1.5, 3, &opts));
+    }
+
+    #[test]
+    fn jacobi_diagonalizes() {
+        let a = vec![vec![4.0, 1.0, -2.0], vec![1.0, 2.0, 0.5], vec![-2.0, 0.5, 3.0]];
+        let (eig, v) = jacobi_eigen(&a);
+        for (j, lambda) in eig.iter().enumerate() {
+            for i in 0..3 {
+                let av: f64 = (0..3).map(|k| a[i][k] * v[k][j]).sum();
+                assert!((av - lambda * v[i][j]).abs() < 1e-12, "A·v ≠ λ·v for λ = {}", lambda);
+            }
+            for k in 0..3 {
+                let dot: f64 = (0..3).map(|i| v[i][j] * v[i][k]).sum();
+                assert!((dot - if j == k { 1.0 } else { 0.0 }).abs() < 1e-12);
+            }
+        }
+        // the trace is kept
+        assert!((eig.iter().sum::<f64>() - 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn seeded_runs_repeat() {
+        let opts = SolveOptions { max_iter: 20, ..Default::default() };
+        let a = cma_es(walled, &start(0.5), 2, 0.5, 9, &opts);
+        let b = cma_es(walled, &start(0.5), 2, 0.5, 9, &opts);
+        assert_eq!(a.history, b.history);
+        let a = pso(walled, &start(0.5), 2, 10, 1.0, 9, &opts);
+        let b = pso(walled, &start(0.5), 2, 10, 1.0, 9, &opts);
+        assert_eq!(a.history, b.history);
+    }
+}
@@ -0,0 +1,506 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::{Div, Mul};
+
+use slut::complex::c64;
+use slut::dimension::{ConstCheck, Dimension, SquareDimension};
+use slut::si::{
+    Ampere, Atmosphere, Bar, Candela, Celsius, Centimeter, Century, CubicMeter, Dalton, Day, ElectronVolt, Fahrenheit,
+    Gallon, Gram, Hertz, Horsepower, Hour, Joule, Kelvin, Kilogram, Kilometer, KilometersPerHour, Lbf, LightYear, Liter,
+    Meter, MetersPerSecond, MetersPerSecondSquared, Micrometer, Microsecond, Milliliter, Millimeter, Millisecond, Minute,
+    Mole, Nanosecond, Newton, Pascal, Pound, Second, SquareMeter, Watt, Year, Ångström,
+};
+use slut::tensor::*;
+use slut::units::{Unit, Unitless};
+
+use crate::{N, Params};
+
+/// Exponents of a slut `Dimension`, in its order: length, mass, time,
+/// temperature, current, amount, luminous intensity.
+///
+/// Values carry their dimension as the `Dimension` parameter of a slut
+/// `Scalar`, so mismatches do not compile. This is the same information at
+/// runtime, for what is only known once a file is read: the units of a CSV
+/// header, the variables of a π analysis, the dimensions of a `Dataset`.
+/// `Dimensional::DIM` gives it for a slut dimension, which is how a typed
+/// trainer is checked against loaded data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dim(pub [i32; 7]);
+
+const SYMBOLS: [&str; 7] = ["m", "kg", "s", "K", "A", "mol", "cd"];
+
+impl Dim {
+    pub const NONE: Dim = Dim([0, 0, 0, 0, 0, 0, 0]);
+
+    pub const fn same(self, other: Dim) -> bool {
+        self.quotient(other).is_none()
+    }
+
+    pub const fn is_none(&self) -> bool {
+        let mut i = 0;
+        while i < 7 {
+            if self.0[i] != 0 {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    pub const fn product(self, other: Dim) -> Dim {
+        let mut e = self.0;
+        let mut i = 0;
+        while i < 7 {
+            e[i] += other.0[i];
+            i += 1;
+        }
+        Dim(e)
+    }
+
+    pub const fn quotient(self, other: Dim) -> Dim {
+        self.product(other.powi(-1))
+    }
+
+    pub const fn powi(self, n: i32) -> Dim {
+        let mut e = self.0;
+        let mut i = 0;
+        while i < 7 {
+            e[i] *= n;
+            i += 1;
+        }
+        Dim(e)
+    }
+
+    /// `Err` naming `what` unless `self` is `expected`.
+    pub fn expect(self, expected: Dim, what: &str) -> Result<(), DimError> {
+        if self == expected {
+            Ok(())
+        } else {
+            Err(DimError { what: what.to_string(), expected, found: self })
+        }
+    }
+}
+
+impl Mul for Dim {
+    type Output = Dim;
+
+    fn mul(self, rhs: Dim) -> Dim {
+        self.product(rhs)
+    }
+}
+
+impl Div for Dim {
+    type Output = Dim;
+
+    fn div(self, rhs: Dim) -> Dim {
+        self.quotient(rhs)
+    }
+}
+
+impl fmt::Display for Dim {
+    // e.g. `kg·m·s⁻²`, `1` when dimensionless
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_none() {
+            return write!(f, "1");
+        }
+        // mass first, as in kg·m²
+        let mut first = true;
+        for i in [1, 0, 2, 3, 4, 5, 6] {
+            let e = self.0[i];
+            if e == 0 {
+                continue;
+            }
+            if !first {
+                write!(f, "·")?;
+            }
+            first = false;
+            write!(f, "{}", SYMBOLS[i])?;
+            if e != 1 {
+                write!(f, "{}", superscript(e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn superscript(e: i32) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    let mut s = String::new();
+    if e < 0 {
+        s.push('⁻');
+    }
+    for c in e.unsigned_abs().to_string().chars() {
+        s.push(DIGITS[c.to_digit(10).unwrap() as usize]);
+    }
+    s
+}
+
+/// Two dimensions that were required to agree did not.
+#[derive(Clone, Debug)]
+pub struct DimError {
+    pub what: String,
+    pub expected: Dim,
+    pub found: Dim,
+}
+
+impl fmt::Display for DimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dimension mismatch in {}: expected {}, found {}", self.what, self.expected, self.found)
+    }
+}
+
+impl Error for DimError {}
+
+/// A slut dimension whose exponents can be read at runtime.
+///
+/// Implemented for every `Dimension`, so any of slut's aliases (`Length`,
+/// `slut::si::Velocity`, …) or products of them can type a trainer or a
+/// dataset.
+pub trait Dimensional: Copy + Default + fmt::Debug + Send + Sync + 'static {
+    const DIM: Dim;
+}
+
+impl<const L: i32, const M: i32, const T: i32, const K: i32, const I: i32, const N: i32, const J: i32> Dimensional
+    for Dimension<L, M, T, K, I, N, J>
+{
+    const DIM: Dim = Dim([L, M, T, K, I, N, J]);
+}
+
+/// `[Self]/[X]^P`, the dimension of the coefficient of `x^P` in a fit of
+/// targets in `Self` on inputs in `X`.
+pub trait PerPower<X, const P: i32> {
+    type Output;
+}
+
+impl<
+    const L1: i32, const M1: i32, const T1: i32, const K1: i32, const I1: i32, const N1: i32, const J1: i32,
+    const L2: i32, const M2: i32, const T2: i32, const K2: i32, const I2: i32, const N2: i32, const J2: i32,
+    const P: i32,
+> PerPower<Dimension<L2, M2, T2, K2, I2, N2, J2>, P> for Dimension<L1, M1, T1, K1, I1, N1, J1>
+where
+    (): ConstCheck<{ L1 - P * L2 }>,
+    (): ConstCheck<{ M1 - P * M2 }>,
+    (): ConstCheck<{ T1 - P * T2 }>,
+    (): ConstCheck<{ K1 - P * K2 }>,
+    (): ConstCheck<{ I1 - P * I2 }>,
+    (): ConstCheck<{ N1 - P * N2 }>,
+    (): ConstCheck<{ J1 - P * J2 }>,
+{
+    type Output = Dimension<
+        { L1 - P * L2 },
+        { M1 - P * M2 },
+        { T1 - P * T2 },
+        { K1 - P * K2 },
+        { I1 - P * I2 },
+        { N1 - P * N2 },
+        { J1 - P * J2 },
+    >;
+}
+
+/// Dimensions of the step quantities when training on targets in `Self`
+/// with loss values in `L`, taken for the constant term, whose coefficient
+/// is in `[Self]`.
+pub trait TrainingDims<L> {
+    /// `[Y]²/[L]`, the learning rate turning a loss gradient into a step.
+    type Lr: Dimensional;
+    /// `[L]/[Y]`, the loss gradient of a coefficient in `[Y]`.
+    type Grad: Dimensional;
+}
+
+impl<
+    const L1: i32, const M1: i32, const T1: i32, const K1: i32, const I1: i32, const N1: i32, const J1: i32,
+    const L2: i32, const M2: i32, const T2: i32, const K2: i32, const I2: i32, const N2: i32, const J2: i32,
+> TrainingDims<Dimension<L2, M2, T2, K2, I2, N2, J2>> for Dimension<L1, M1, T1, K1, I1, N1, J1>
+where
+    (): ConstCheck<{ 2 * L1 - L2 }>,
+    (): ConstCheck<{ 2 * M1 - M2 }>,
+    (): ConstCheck<{ 2 * T1 - T2 }>,
+    (): ConstCheck<{ 2 * K1 - K2 }>,
+    (): ConstCheck<{ 2 * I1 - I2 }>,
+    (): ConstCheck<{ 2 * N1 - N2 }>,
+    (): ConstCheck<{ 2 * J1 - J2 }>,
+    (): ConstCheck<{ L2 - L1 }>,
+    (): ConstCheck<{ M2 - M1 }>,
+    (): ConstCheck<{ T2 - T1 }>,
+    (): ConstCheck<{ K2 - K1 }>,
+    (): ConstCheck<{ I2 - I1 }>,
+    (): ConstCheck<{ N2 - N1 }>,
+    (): ConstCheck<{ J2 - J1 }>,
+{
+    type Lr = Dimension<
+        { 2 * L1 - L2 },
+        { 2 * M1 - M2 },
+        { 2 * T1 - T2 },
+        { 2 * K1 - K2 },
+        { 2 * I1 - I2 },
+        { 2 * N1 - N2 },
+        { 2 * J1 - J2 },
+    >;
+    type Grad = Dimension<{ L2 - L1 }, { M2 - M1 }, { T2 - T1 }, { K2 - K1 }, { I2 - I1 }, { N2 - N1 }, { J2 - J1 }>;
+}
+
+/// Learning rate of a trainer on targets in `Y` with a loss in `L`.
+pub type Lr<Y, L> = Scalar<f64, <Y as TrainingDims<L>>::Lr>;
+
+/// Loss gradient of the constant term for targets in `Y` and a loss in `L`.
+pub type LossGrad<Y, L> = Scalar<f64, <Y as TrainingDims<L>>::Grad>;
+
+/// The coefficient of `x^P` in a fit of `Y` on `X`.
+pub type Monomial<Y, X, const P: i32> = Scalar<f64, <Y as PerPower<X, P>>::Output>;
+
+/// `[D]²`, the dimension of a squared-error loss on targets in `D`.
+pub type Sq<D> = <D as SquareDimension>::Output;
+
+/// `[y]/[x]^k` for `k = 0..N`, the dimensions of monomial coefficients.
+pub fn monomial_dims(x: Dim, y: Dim) -> [Dim; N] {
+    std::array::from_fn(|k| y.quotient(x.powi(k as i32)))
+}
+
+/// `v m`, or just `v` when dimensionless.
+pub fn format_value(v: f64, d: Dim) -> String {
+    if d.is_none() { format!("{}", v) } else { format!("{} {}", v, d) }
+}
+
+/// A typed scalar with its unit, as `format_value`.
+pub fn format_scalar<D: Dimensional>(s: Scalar<f64, D>) -> String {
+    format_value(s.raw(), D::DIM)
+}
+
+/// `c₀ m, c₁ m·s⁻¹, …` for the first `count` parameters.
+pub fn format_coeffs(params: &Params, dims: &[Dim]) -> String {
+    let parts: Vec<String> = dims
+        .iter()
+        .enumerate()
+        .map(|(k, d)| format_value(params.get_at(0, k, 0).raw(), *d))
+        .collect();
+    format!("[{}]", parts.join(", "))
+}
+
+/// A unit as `si = value · scale + offset` in the canonical SI unit of `dim`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnitSpec {
+    pub scale: f64,
+    pub offset: f64,
+    pub dim: Dim,
+}
+
+impl UnitSpec {
+    pub fn to_si(&self, v: f64) -> f64 {
+        v * self.scale + self.offset
+    }
+}
+
+/// A unit string that could not be parsed.
+#[derive(Clone, Debug)]
+pub struct UnitError {
+    pub unit: String,
+    pub reason: String,
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bad unit `{}`: {}", self.unit, self.reason)
+    }
+}
+
+impl Error for UnitError {}
+
+// A slut unit as its symbol and `UnitSpec`.
+fn unit<U: Unit>() -> (&'static str, UnitSpec)
+where
+    U::Dimension: Dimensional,
+{
+    let offset = U::to_base(c64::new(0.0, 0.0)).re();
+    let scale = U::to_base(c64::new(1.0, 0.0)).re() - offset;
+    (U::symbol(), UnitSpec { scale, offset, dim: U::Dimension::DIM })
+}
+
+// the slut units a header can name, by their slut symbols
+const UNITS: &[fn() -> (&'static str, UnitSpec)] = &[
+    unit::<Micrometer>,
+    unit::<Millimeter>,
+    unit::<Centimeter>,
+    unit::<Meter>,
+    unit::<Kilometer>,
+    unit::<LightYear>,
+    unit::<Ångström>,
+    unit::<Nanosecond>,
+    unit::<Microsecond>,
+    unit::<Millisecond>,
+    unit::<Second>,
+    unit::<Minute>,
+    unit::<Hour>,
+    unit::<Day>,
+    unit::<Year>,
+    unit::<Century>,
+    unit::<Gram>,
+    unit::<Kilogram>,
+    unit::<Pound>,
+    unit::<Dalton>,
+    unit::<Kelvin>,
+    unit::<Celsius>,
+    unit::<Fahrenheit>,
+    unit::<Ampere>,
+    unit::<Mole>,
+    unit::<Candela>,
+    unit::<Newton>,
+    unit::<Lbf>,
+    unit::<MetersPerSecond>,
+    unit::<KilometersPerHour>,
+    unit::<MetersPerSecondSquared>,
+    unit::<Joule>,
+    unit::<ElectronVolt>,
+    unit::<Pascal>,
+    unit::<Bar>,
+    unit::<Atmosphere>,
+    unit::<Watt>,
+    unit::<Horsepower>,
+    unit::<SquareMeter>,
+    unit::<CubicMeter>,
+    unit::<Liter>,
+    unit::<Milliliter>,
+    unit::<Gallon>,
+    unit::<Hertz>,
+];
+
+// a slut unit by symbol, `1` for slut's `Unitless`
+fn lookup(sym: &str) -> Option<UnitSpec> {
+    if sym == "1" {
+        return Some(unit::<Unitless>().1);
+    }
+    UNITS.iter().map(|u| u()).find(|(s, _)| *s == sym).map(|(_, spec)| spec)
+}
+
+/// Parse a unit such as `ms`, `km/h`, `m/s^2`, `kg·m²/s²` or `°C` into
+/// slut's units.
+///
+/// Every symbol names one of the units of `slut::si`, whose scale, offset
+/// and dimension are used as they are; there are no SI prefixes beyond the
+/// units slut defines. A unit slut has whole, such as `m/s`, is taken
+/// directly. Otherwise factors are separated by `*`, `·` or `.`, every `/`
+/// divides by the factors after it, and exponents are written `^-2`, `-2`
+/// or `⁻²`. Offsets (Celsius, Fahrenheit) only apply to a unit on its own;
+/// inside a product the unit stands for a temperature difference.
+pub fn parse_unit(unit: &str) -> Result<UnitSpec, UnitError> {
+    let err = |reason: String| UnitError { unit: unit.to_string(), reason };
+    if let Some(u) = lookup(unit.trim()) {
+        return Ok(u);
+    }
+    let ascii: String = unit
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '⁻' => '-',
+            'μ' => 'µ',
+            '¹' => '1',
+            '²' => '2',
+            '³' => '3',
+            '⁰'..='⁹' => char::from_digit(c as u32 - '⁰' as u32, 10).unwrap_or(c),
+            _ => c,
+        })
+        .collect();
+    if ascii.is_empty() {
+        return Err(err("empty unit".to_string()));
+    }
+
+    let mut total = UnitSpec { scale: 1.0, offset: 0.0, dim: Dim::NONE };
+    let mut factors = 0;
+    for (i, part) in ascii.split('/').enumerate() {
+        let sign = if i == 0 { 1 } else { -1 };
+        for factor in part.split(['*', '·', '.', ' ']).filter(|f| !f.is_empty()) {
+            // split off the exponent
+            let split = factor
+                .find('^')
+                .or_else(|| factor.find(|c: char| c == '-' || c.is_ascii_digit()).filter(|&k| k > 0))
+                .unwrap_or(factor.len());
+            let (sym, exp) = factor.split_at(split);
+            let exp = exp.trim_start_matches('^');
+            let exp: i32 = if exp.is_empty() {
+                1
+            } else {
+                exp.parse().map_err(|_| err(format!("bad exponent in `{}`", factor)))?
+            };
+            let u = lookup(sym).ok_or_else(|| err(format!("unknown unit `{}`", sym)))?;
+            let exp = exp * sign;
+            total.scale *= u.scale.powi(exp);
+            total.dim = total.dim * u.dim.powi(exp);
+            total.offset = if exp == 1 { u.offset } else { 0.0 };
+            factors += 1;
+        }
+    }
+    if factors != 1 {
+        total.offset = 0.0;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use slut::dimension::{Amount, Length, Mass, Temperature, Time};
+    use slut::si::Force;
+
+    use super::*;
+
+    fn unit(u: &str) -> UnitSpec {
+        parse_unit(u).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn slut_units() {
+        assert_eq!(unit("ms"), UnitSpec { scale: 1e-3, offset: 0.0, dim: Time::DIM });
+        assert_eq!(unit("N").dim, Force::DIM);
+        assert!(close(unit("lbf").scale, 4.44822));
+        assert!(close(unit("µm").scale, 1e-6));
+        assert_eq!(unit("μm"), unit("µm"));
+        assert_eq!(unit("min").scale, 60.0);
+        assert!(close(unit("mm").scale, 1e-3));
+        assert!(unit("1").dim.is_none());
+        // slut's compound units are taken whole
+        assert!(close(unit("km/h").scale, 1.0 / 3.6));
+        assert_eq!(unit("m/s²").dim, unit("m/s^2").dim);
+    }
+
+    #[test]
+    fn products_quotients_and_exponents() {
+        let accel = Length::DIM / Time::DIM.powi(2);
+        for u in ["m/s^2", "m/s2", "m·s⁻²", "m*s^-2", "m s-2"] {
+            assert_eq!(unit(u).dim, accel, "{}", u);
+        }
+        let energy = unit("kg·m²/s²");
+        assert_eq!(energy.dim, unit("J").dim);
+        assert!(close(energy.scale, 1.0));
+        // every `/` divides by all factors after it
+        assert_eq!(unit("J/mol/K").dim, unit("J").dim / Amount::DIM / Temperature::DIM);
+        assert!(close(unit("km/h").to_si(36.0), 10.0));
+    }
+
+    #[test]
+    fn offsets_only_on_their_own() {
+        assert!(close(unit("°C").to_si(25.0), 298.15));
+        assert!((unit("°F").to_si(212.0) - 373.15).abs() < 1e-5);
+        // a temperature difference inside a product
+        assert_eq!(unit("J/°C").offset, 0.0);
+        assert_eq!(unit("°C^2").offset, 0.0);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (u, reason) in [("", "empty"), ("furlong", "unknown unit"), ("kN", "unknown unit"), ("m^x", "bad exponent")] {
+            let e = parse_unit(u).unwrap_err();
+            assert!(e.reason.contains(reason), "{}: {}", u, e);
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!((Mass::DIM * Length::DIM / Time::DIM.powi(2)).to_string(), "kg·m·s⁻²");
+        assert_eq!(Dim::NONE.to_string(), "1");
+        assert_eq!(superscript(-12), "⁻¹²");
+        assert!(Length::DIM.expect(Time::DIM, "x").is_err());
+        assert!(Length::DIM.expect(Length::DIM, "x").is_ok());
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use slut::{dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+use crate::loss::Real;
+use crate::{N, Params};
+
+/// The parameter vector lifted into duals.
+pub type DualParams = [Dual<Dimensionless>; N];
+
+/// Forward-mode dual number `re + eps·ε` with `ε² = 0`.
+///
+/// Both parts carry the slut dimension `D`, so adding a length to a time
+/// still fails to compile. Derivatives are taken with respect to
+/// dimensionless parameters, which keeps `eps` in the same dimension as `re`.
+pub struct Dual<D> {
+    pub re: Scalar<f64, D>,
+    pub eps: Scalar<f64, D>,
+}
+
+impl<D> Clone for Dual<D>
+where
+    Scalar<f64, D>: Copy,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D> Copy for Dual<D> where Scalar<f64, D>: Copy {}
+
+impl<D> Dual<D> {
+    pub fn new(re: Scalar<f64, D>, eps: Scalar<f64, D>) -> Self {
+        Dual { re, eps }
+    }
+}
+
+impl Dual<Dimensionless> {
+    /// A value that does not depend on the differentiation variable.
+    pub fn constant(re: Scalar<f64, Dimensionless>) -> Self {
+        Dual { re, eps: Scalar::zero() }
+    }
+
+    /// The differentiation variable itself.
+    pub fn variable(re: Scalar<f64, Dimensionless>) -> Self {
+        Dual { re, eps: dless!(1.0) }
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        let d = if n == 0 { 0.0 } else { n as f64 * self.re.raw().powi(n - 1) };
+        Dual { re: dless!(self.re.raw().powi(n)), eps: self.eps * dless!(d) }
+    }
+
+    /// Absolute value. The derivative at zero is taken as zero.
+    pub fn mag(self) -> Self {
+        let r = self.re.raw();
+        let s = if r > 0.0 { 1.0 } else if r < 0.0 { -1.0 } else { 0.0 };
+        Dual { re: self.re.mag(), eps: self.eps * dless!(s) }
+    }
+
+    // f(re) + f'(re)·eps
+    fn apply(self, f: f64, d: f64) -> Self {
+        Dual { re: dless!(f), eps: self.eps * dless!(d) }
+    }
+
+    pub fn exp(self) -> Self {
+        let e = self.re.raw().exp();
+        self.apply(e, e)
+    }
+
+    pub fn ln(self) -> Self {
+        let r = self.re.raw();
+        self.apply(r.ln(), 1.0 / r)
+    }
+
+    pub fn tanh(self) -> Self {
+        let t = self.re.raw().tanh();
+        self.apply(t, 1.0 - t * t)
+    }
+
+    pub fn sqrt(self) -> Self {
+        let q = self.re.raw().sqrt();
+        self.apply(q, 0.5 / q)
+    }
+}
+
+impl<D> fmt::Display for Dual<D>
+where
+    Scalar<f64, D>: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} + {}ε", self.re, self.eps)
+    }
+}
+
+impl<D> Add for Dual<D>
+where
+    Scalar<f64, D>: Add<Output = Scalar<f64, D>>,
+{
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Dual { re: self.re + rhs.re, eps: self.eps + rhs.eps }
+    }
+}
+
+impl<D> Sub for Dual<D>
+where
+    Scalar<f64, D>: Sub<Output = Scalar<f64, D>>,
+{
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Dual { re: self.re - rhs.re, eps: self.eps - rhs.eps }
+    }
+}
+
+impl<D> Neg for Dual<D>
+where
+    Scalar<f64, D>: Sub<Output = Scalar<f64, D>>,
+{
+    type Output = Self;
+    fn neg(self) -> Self {
+        Dual { re: Scalar::zero() - self.re, eps: Scalar::zero() - self.eps }
+    }
+}
+
+// scaling by a dimensionless dual keeps the dimension of the left side
+impl<D> Mul<Dual<Dimensionless>> for Dual<D>
+where
+    Scalar<f64, D>: Copy
+        + Add<Output = Scalar<f64, D>>
+        + Mul<Scalar<f64, Dimensionless>, Output = Scalar<f64, D>>,
+{
+    type Output = Self;
+    fn mul(self, rhs: Dual<Dimensionless>) -> Self {
+        Dual {
+            re: self.re * rhs.re,
+            eps: self.re * rhs.eps + self.eps * rhs.re,
+        }
+    }
+}
+
+impl<D> Div<Dual<Dimensionless>> for Dual<D>
+where
+    Scalar<f64, D>: Copy
+        + Sub<Output = Scalar<f64, D>>
+        + Mul<Scalar<f64, Dimensionless>, Output = Scalar<f64, D>>
+        + Div<Scalar<f64, Dimensionless>, Output = Scalar<f64, D>>,
+{
+    type Output = Self;
+    fn div(self, rhs: Dual<Dimensionless>) -> Self {
+        Dual {
+            re: self.re / rhs.re,
+            eps: (self.eps * rhs.re - self.re * rhs.eps) / (rhs.re * rhs.re),
+        }
+    }
+}
+
+impl Real for Dual<Dimensionless> {
+    fn raw(&self) -> f64 {
+        self.re.raw()
+    }
+
+    fn lift(&self, v: f64) -> Self {
+        Dual::constant(dless!(v))
+    }
+
+    fn exp(self) -> Self {
+        Dual::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        Dual::ln(self)
+    }
+
+    fn tanh(self) -> Self {
+        Dual::tanh(self)
+    }
+
+    fn sqrt(self) -> Self {
+        Dual::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        self.mag()
+    }
+}
+
+/// Lift the coefficients into duals, seeding the derivative along `dir`.
+pub fn seed(c: &Params, dir: &Params) -> DualParams {
+    std::array::from_fn(|k| Dual::new(c.get_at(0, k, 0), dir.get_at(0, k, 0)))
+}
+
+/// Dual equivalent of `dot!(coeffs, inputs)` for constant inputs.
+pub fn dot(coeffs: &DualParams, inputs: &Params) -> Dual<Dimensionless> {
+    let mut acc = Dual::constant(Scalar::zero());
+    for (k, c) in coeffs.iter().enumerate() {
+        acc = acc + *c * Dual::constant(inputs.get_at(0, k, 0));
+    }
+    acc
+}
+
+/// Exact derivative of `f` at `c` along `dir`, in one forward pass.
+pub fn directional<F>(f: F, c: &Params, dir: &Params) -> Scalar<f64, Dimensionless>
+where
+    F: Fn(&DualParams) -> Dual<Dimensionless>,
+{
+    f(&seed(c, dir)).eps
+}
+
+/// Full gradient of `f` at `c` from `N` forward passes, one per basis vector.
+pub fn gradient<F>(f: F, c: &Params) -> Params
+where
+    F: Fn(&DualParams) -> Dual<Dimensionless>,
+{
+    let mut g = Params::zero();
+    for k in 0..N {
+        let mut dir = Params::zero();
+        dir.set_at(0, k, 0, dless!(1.0));
+        g.set_at(0, k, 0, directional(&f, c, &dir));
+    }
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // d/dx of `f` at `x` from one forward pass
+    fn derivative(f: impl Fn(Dual<Dimensionless>) -> Dual<Dimensionless>, x: f64) -> f64 {
+        f(Dual::variable(dless!(x))).eps.raw()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn elementary_functions() {
+        let x: f64 = 0.7;
+        assert!(close(derivative(|d| d.exp(), x), x.exp()));
+        assert!(close(derivative(|d| d.ln(), x), 1.0 / x));
+        assert!(close(derivative(|d| d.tanh(), x), 1.0 / x.cosh().powi(2)));
+        assert!(close(derivative(|d| d.sqrt(), x), 0.5 / x.sqrt()));
+        assert!(close(derivative(|d| d.powi(3), x), 3.0 * x * x));
+        assert_eq!(derivative(|d| (-d).mag(), x), 1.0);
+    }
+
+    #[test]
+    fn chain_and_quotient_rules() {
+        let x: f64 = 1.3;
+        let one = Dual::constant(dless!(1.0));
+        // d/dx ln(1 + e^{x²}) = 2x·e^{x²} / (1 + e^{x²})
+        let s = (x * x).exp();
+        assert!(close(derivative(|d| ((d * d).exp() + one).ln(), x), 2.0 * x * s / (1.0 + s)));
+        // d/dx x / (1 + x²) = (1 - x²) / (1 + x²)²
+        let q = 1.0 + x * x;
+        assert!(close(derivative(|d| d / (one + d * d), x), (1.0 - x * x) / (q * q)));
+    }
+
+    #[test]
+    fn gradient_of_a_quadratic_form() {
+        // f(c) = Σ (k+1)·c_k², ∂f/∂c_k = 2(k+1)·c_k
+        let c = crate::from_array(std::array::from_fn(|k| 0.5 - k as f64));
+        let g = gradient(
+            |d: &DualParams| {
+                d.iter().enumerate().fold(Dual::constant(Scalar::zero()), |acc, (k, &v)| {
+                    acc + v * v * Dual::constant(dless!(k as f64 + 1.0))
+                })
+            },
+            &c,
+        );
+        let (g, c) = (crate::to_array(&g), crate::to_array(&c));
+        assert!(g.iter().zip(c).enumerate().all(|(k, (&g, c))| close(g, 2.0 * (k as f64 + 1.0) * c)), "{:?}", g);
+    }
+}
@@ -0,0 +1,66 @@
+use slut::{dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+use crate::data::Dataset;
+use crate::loss::{Loss, Mse};
+use crate::model::Model;
+use crate::Params;
+
+/// Exact gradient of `loss` over `data` with respect to every parameter.
+///
+/// With residuals `r_i = y(x_i) - t_i`, the chain rule gives
+/// `∂L/∂c_k = Σ ∂L/∂r_i · ∂y(x_i)/∂c_k`. For the MSE the first factor is
+/// `2/M r_i`, and for the polynomial the last one is just `x_i^k`. Returns
+/// `None` when the model has no closed-form `param_grad`.
+pub fn loss_grad<M>(
+    model: &M,
+    coeffs: &Params,
+    data: &Dataset,
+    loss: &dyn Loss,
+) -> Option<Params>
+where
+    M: Model + ?Sized,
+{
+    let mut grads = Params::zero();
+    let weights = loss.total_grad(&residuals(model, coeffs, data));
+
+    for ((x, _), w) in data.iter().zip(weights) {
+        let dy = model.param_grad(coeffs, x)?;
+        grads += dy * dless!(w);
+    }
+
+    Some(grads)
+}
+
+/// Exact gradient of the MSE, `loss_grad` with the squared error.
+pub fn mse_grad<M>(
+    model: &M,
+    coeffs: &Params,
+    data: &Dataset,
+) -> Option<Params>
+where
+    M: Model + ?Sized,
+{
+    loss_grad(model, coeffs, data, &Mse)
+}
+
+/// `y(x_i) - t_i` for every sample.
+pub fn residuals<M>(model: &M, coeffs: &Params, data: &Dataset) -> Vec<f64>
+where
+    M: Model + ?Sized,
+{
+    data.iter().map(|(x, t)| model.forward(coeffs, x).raw() - t).collect()
+}
+
+/// Central finite difference of `f` along coefficient `k`.
+///
+/// Only kept as a fallback for losses that have no closed-form gradient.
+pub fn finite_diff<F>(f: F, c: &Params, k: usize, h: f64) -> Scalar<f64, Dimensionless>
+where
+    F: Fn(&Params) -> Scalar<f64, Dimensionless>,
+{
+    let mut c_plus = *c;
+    let mut c_minus = *c;
+    c_plus.set_at(0, k, 0, c.get_at(0, k, 0) + dless!(h));
+    c_minus.set_at(0, k, 0, c.get_at(0, k, 0) - dless!(h));
+    dless!((f(&c_plus) - f(&c_minus)).raw() / (2.0 * h))
+}
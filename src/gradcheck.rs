@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::fmt;
+
+use slut::complex::c64;
+
+use crate::complex::{self, ComplexParams};
+use crate::{from_array, to_array, N, Params};
+
+/// Numerical differentiation the analytic gradient is compared against.
+#[derive(Clone, Copy, Debug)]
+pub enum Method {
+    /// `(f(c + h·e_k) - f(c - h·e_k)) / 2h`, accurate to `O(h²)` but
+    /// limited by cancellation.
+    Central { h: f64 },
+    /// `Im f(c + i·h·e_k) / h`, exact to machine precision for analytic `f`.
+    ComplexStep { h: f64 },
+}
+
+impl Method {
+    pub fn central() -> Self {
+        Method::Central { h: 1e-6 }
+    }
+
+    pub fn complex_step() -> Self {
+        Method::ComplexStep { h: 1e-20 }
+    }
+
+    pub fn step(&self) -> f64 {
+        match *self {
+            Method::Central { h } | Method::ComplexStep { h } => h,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Central { .. } => "central",
+            Method::ComplexStep { .. } => "complex-step",
+        }
+    }
+}
+
+/// A coefficient passes when its absolute error is below `atol` or its
+/// relative error below `rtol`.
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    pub rtol: f64,
+    pub atol: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance { rtol: 1e-6, atol: 1e-10 }
+    }
+}
+
+/// Comparison for one coefficient.
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub k: usize,
+    pub analytic: f64,
+    pub numeric: f64,
+    /// `|analytic - numeric| / max(|analytic|, |numeric|)`, zero when both are.
+    pub rel_error: f64,
+    pub ok: bool,
+}
+
+/// Comparison of a whole gradient at one point.
+#[derive(Clone, Debug)]
+pub struct GradCheck {
+    /// Where the gradient was taken, e.g. `polynomial at x = 0.5, params 1`.
+    pub label: String,
+    pub method: Method,
+    pub entries: Vec<Entry>,
+}
+
+impl GradCheck {
+    pub fn passed(&self) -> bool {
+        self.entries.iter().all(|e| e.ok)
+    }
+
+    pub fn max_rel_error(&self) -> f64 {
+        self.entries.iter().map(|e| e.rel_error).fold(0.0, f64::max)
+    }
+}
+
+impl fmt::Display for GradCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({}), max relative error {:e}", self.label, self.method.name(), self.max_rel_error())?;
+        for e in &self.entries {
+            writeln!(f, "  c{}: analytic {:+e}, numeric {:+e}, relative error {:e}{}",
+                e.k, e.analytic, e.numeric, e.rel_error, if e.ok { "" } else { "  <- FAIL" })?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a gradient check failed.
+#[derive(Clone, Debug)]
+pub enum GradCheckError {
+    /// Every check that had a coefficient out of tolerance.
+    Mismatch(Vec<GradCheck>),
+}
+
+impl fmt::Display for GradCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GradCheckError::Mismatch(checks) => {
+                writeln!(f, "gradient check failed at {} point(s):", checks.len())?;
+                for c in checks {
+                    write!(f, "{}", c)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for GradCheckError {}
+
+/// Compare the first `n` entries of an analytic gradient to a numerical one.
+pub fn compare(label: String, method: Method, analytic: &Params, numeric: &[f64; N], n: usize, tol: &Tolerance) -> GradCheck {
+    let analytic = to_array(analytic);
+    let entries = (0..n)
+        .map(|k| {
+            let (a, b) = (analytic[k], numeric[k]);
+            let err = (a - b).abs();
+            let scale = a.abs().max(b.abs());
+            let rel_error = if scale > 0.0 { err / scale } else { 0.0 };
+            Entry { k, analytic: a, numeric: b, rel_error, ok: err <= tol.atol || rel_error <= tol.rtol }
+        })
+        .collect();
+    GradCheck { label, method, entries }
+}
+
+/// Central differences of a real `f` along the first `n` coefficients.
+pub fn central<F>(f: F, c: &Params, n: usize, h: f64) -> [f64; N]
+where
+    F: Fn(&Params) -> f64,
+{
+    let base = to_array(c);
+    std::array::from_fn(|k| {
+        if k >= n {
+            return 0.0;
+        }
+        let (mut cp, mut cm) = (base, base);
+        cp[k] += h;
+        cm[k] -= h;
+        (f(&from_array(cp)) - f(&from_array(cm))) / (2.0 * h)
+    })
+}
+
+/// Complex-step derivatives of `f` along the first `n` coefficients.
+pub fn complex_step<F>(f: F, c: &Params, n: usize, h: f64) -> [f64; N]
+where
+    F: Fn(&ComplexParams) -> c64,
+{
+    std::array::from_fn(|k| if k < n { f(&complex::perturb(c, k, h)).im() / h } else { 0.0 })
+}
+
+fn collect(checks: Vec<GradCheck>) -> Result<Vec<GradCheck>, GradCheckError> {
+    let failed: Vec<GradCheck> = checks.iter().filter(|c| !c.passed()).cloned().collect();
+    if failed.is_empty() { Ok(checks) } else { Err(GradCheckError::Mismatch(failed)) }
+}
+
+/// Check `grad` against central differences of `f` at every point, over
+/// the first `n` coefficients.
+///
+/// Returns every comparison, or `Err` listing each coefficient out of
+/// tolerance, so `gradcheck(...).unwrap()` in a test prints the full table
+/// on failure.
+pub fn gradcheck<G, F>(grad: G, f: F, points: &[Params], n: usize, h: f64, tol: &Tolerance) -> Result<Vec<GradCheck>, GradCheckError>
+where
+    G: Fn(&Params) -> Params,
+    F: Fn(&Params) -> f64,
+{
+    let method = Method::Central { h };
+    let checks = points
+        .iter()
+        .enumerate()
+        .map(|(i, c)| compare(format!("point {}", i), method, &grad(c), &central(&f, c, n, h), n, tol))
+        .collect();
+    collect(checks)
+}
+
+/// `gradcheck` against complex-step derivatives of `f`, which must be the
+/// complex extension of the function whose gradient is `grad`.
+pub fn gradcheck_complex<G, F>(grad: G, f: F, points: &[Params], n: usize, h: f64, tol: &Tolerance) -> Result<Vec<GradCheck>, GradCheckError>
+where
+    G: Fn(&Params) -> Params,
+    F: Fn(&ComplexParams) -> c64,
+{
+    let method = Method::ComplexStep { h };
+    let checks = points
+        .iter()
+        .enumerate()
+        .map(|(i, c)| compare(format!("point {}", i), method, &grad(c), &complex_step(&f, c, n, h), n, tol))
+        .collect();
+    collect(checks)
+}
+
+/// Both sets of checks, failing with every mismatch if either failed.
+pub fn merge(
+    a: Result<Vec<GradCheck>, GradCheckError>,
+    b: Result<Vec<GradCheck>, GradCheckError>,
+) -> Result<Vec<GradCheck>, GradCheckError> {
+    match (a, b) {
+        (Ok(mut a), Ok(b)) => {
+            a.extend(b);
+            Ok(a)
+        }
+        (Err(GradCheckError::Mismatch(mut a)), Err(GradCheckError::Mismatch(b))) => {
+            a.extend(b);
+            Err(GradCheckError::Mismatch(a))
+        }
+        (Err(e), Ok(_)) | (Ok(_), Err(e)) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::curriculum::Curriculum;
+    use crate::data::Dataset;
+    use crate::loss::Real;
+    use crate::model::{Basis, Fourier, Model, Polynomial};
+    use crate::optim::Sgd;
+    use crate::schedule::Constant;
+    use crate::train::{GradMode, Trainer};
+
+    const LOSSES: [&str; 6] = ["mse", "mae", "huber", "logcosh", "quantile", "maxerr"];
+
+    fn models() -> Vec<Box<dyn Model>> {
+        let domain = (0.0, 2.0);
+        let mut m: Vec<Box<dyn Model>> = [Basis::Monomial, Basis::Chebyshev, Basis::Chebyshev2, Basis::Legendre, Basis::Hermite]
+            .into_iter()
+            .map(|b| Box::new(Polynomial::with_basis(6, b, domain)) as Box<dyn Model>)
+            .collect();
+        m.push(Box::new(Fourier::new(7, 4.0)));
+        m
+    }
+
+    fn trainer(model: Box<dyn Model>, loss: &str) -> Trainer {
+        let data = Dataset::grid(|x| x.cos(), 0.0, 0.1, 20);
+        let mut t = Trainer::new(model, data, Box::new(Sgd), Box::new(Constant), Curriculum::off());
+        t.loss = crate::loss::by_name(loss).unwrap();
+        t
+    }
+
+    // random parameters, small enough to keep residuals of order one
+    fn points(n: usize) -> Vec<Params> {
+        let mut rng = StdRng::seed_from_u64(5);
+        (0..3).map(|_| from_array(std::array::from_fn(|k| if k < n { rng.random_range(-0.3..0.3) } else { 0.0 }))).collect()
+    }
+
+    #[test]
+    fn trainer_gradient_matches_numerical_derivatives() {
+        let tol = Tolerance { rtol: 1e-5, atol: 1e-8 };
+        for loss in LOSSES {
+            for model in models() {
+                let name = model.name();
+                let t = trainer(model, loss);
+                let checks = t.gradcheck(&points(t.model.param_count()), &tol).unwrap_or_else(|e| panic!("{} with {}: {}", name, loss, e));
+                assert!(checks.iter().any(|c| matches!(c.method, Method::ComplexStep { .. })), "{} with {}", name, loss);
+            }
+        }
+    }
+
+    #[test]
+    fn gradient_modes_agree() {
+        for loss in LOSSES {
+            for model in models() {
+                let name = model.name();
+                let mut t = trainer(model, loss);
+                for c in points(t.model.param_count()) {
+                    let grad = |t: &mut Trainer, mode| {
+                        t.grad_mode = mode;
+                        to_array(&t.grad(&c))
+                    };
+                    let analytic = grad(&mut t, GradMode::Analytic);
+                    for (mode, rtol) in [(GradMode::Autodiff, 1e-12), (GradMode::Dual, 1e-12), (GradMode::FiniteDiff, 1e-5)] {
+                        let other = grad(&mut t, mode);
+                        for (a, b) in analytic.iter().zip(&other) {
+                            assert!((a - b).abs() <= rtol * a.abs().max(1.0), "{} with {}, {:?}: {} vs {}", name, loss, mode, a, b);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn complex_step_is_exact() {
+        // f(c) = Σ exp(c_k)·tanh(c_k), f' = exp(c)·(tanh c + 1 - tanh² c)
+        let c = from_array(std::array::from_fn(|k| 0.1 * k as f64));
+        let f = |p: &ComplexParams| p.iter().map(|&v| v.exp() * v.tanh()).sum();
+        let grad = |c: &Params| from_array(to_array(c).map(|v| v.exp() * (v.tanh() + 1.0 - v.tanh().powi(2))));
+        let tol = Tolerance { rtol: 1e-15, atol: 0.0 };
+        gradcheck_complex(grad, f, &[c], N, 1e-20, &tol).unwrap();
+    }
+
+    #[test]
+    fn mismatch_lists_the_failing_coefficients() {
+        let c = Params::zero();
+        let wrong = |_: &Params| from_array(std::array::from_fn(|k| if k == 2 { 1.0 } else { 0.0 }));
+        let Err(GradCheckError::Mismatch(checks)) = gradcheck(wrong, |_| 0.0, &[c], N, 1e-6, &Tolerance::default()) else {
+            panic!("expected a mismatch");
+        };
+        let failed: Vec<usize> = checks[0].entries.iter().filter(|e| !e.ok).map(|e| e.k).collect();
+        assert_eq!(failed, [2]);
+    }
+}
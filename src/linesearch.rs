@@ -0,0 +1,268 @@
+use crate::{from_array, to_array, N, Params};
+
+/// How far to move along a descent direction `d` from `c`.
+///
+/// Both rules start from a trial step `t0` and require sufficient decrease
+/// `f(c + t·d) ≤ f(c) + c1·t·∇f·d`. Every evaluation of the objective and of
+/// its gradient is counted, so runs can be compared with fixed learning
+/// rates by cost.
+#[derive(Clone, Copy, Debug)]
+pub enum LineSearch {
+    /// Backtracking, `t` multiplied by `shrink` until the decrease is
+    /// sufficient. When `t0` already is, `t` is divided by `shrink` instead,
+    /// as long as the decrease stays sufficient and the value drops.
+    Armijo { c1: f64, shrink: f64, max_evals: usize },
+    /// Bracketing and zoom until the curvature condition
+    /// `|∇f(c + t·d)·d| ≤ c2·|∇f·d|` holds as well.
+    StrongWolfe { c1: f64, c2: f64, max_evals: usize },
+}
+
+/// The accepted step of a line search.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub t: f64,
+    pub coeffs: Params,
+    pub value: f64,
+    /// Gradient at `coeffs`, when the search had to compute it.
+    pub grad: Option<Params>,
+    pub evaluations: usize,
+    pub grad_evaluations: usize,
+    /// False when the evaluation budget ran out first; the step then is
+    /// the best decrease found, possibly `t = 0`.
+    pub success: bool,
+}
+
+impl LineSearch {
+    pub fn armijo() -> Self {
+        LineSearch::Armijo { c1: 1e-4, shrink: 0.5, max_evals: 40 }
+    }
+
+    pub fn strong_wolfe() -> Self {
+        LineSearch::StrongWolfe { c1: 1e-4, c2: 0.9, max_evals: 40 }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "armijo" => Some(LineSearch::armijo()),
+            "wolfe" => Some(LineSearch::strong_wolfe()),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineSearch::Armijo { .. } => "armijo",
+            LineSearch::StrongWolfe { .. } => "wolfe",
+        }
+    }
+
+    /// Search along `d` from `c`, where `f0` and `g0` are the objective and
+    /// its gradient at `c`. `d` must be a descent direction, `g0·d < 0`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search<F, G>(
+        &self,
+        f: F,
+        grad: G,
+        c: &Params,
+        d: &Params,
+        f0: f64,
+        g0: &Params,
+        t0: f64,
+    ) -> Step
+    where
+        F: Fn(&Params) -> f64,
+        G: Fn(&Params) -> Params,
+    {
+        let (c, d) = (to_array(c), to_array(d));
+        let at = |t: f64| from_array(std::array::from_fn(|k| c[k] + t * d[k]));
+        let slope0 = dot(&to_array(g0), &d);
+        let mut step = Step {
+            t: 0.0,
+            coeffs: from_array(c),
+            value: f0,
+            grad: None,
+            evaluations: 0,
+            grad_evaluations: 0,
+            success: false,
+        };
+        // point and value at t, counted
+        let eval = |t: f64, step: &mut Step| {
+            let x = at(t);
+            step.evaluations += 1;
+            (x, f(&x))
+        };
+
+        match *self {
+            LineSearch::Armijo { c1, shrink, max_evals } => {
+                let armijo = |t: f64, v: f64| v <= f0 + c1 * t * slope0;
+
+                let mut t = t0;
+                let (x, v) = eval(t, &mut step);
+                if armijo(t, v) {
+                    // expanding phase
+                    step = Step { t, coeffs: x, value: v, success: true, ..step };
+                    while step.evaluations < max_evals {
+                        t /= shrink;
+                        let (x, v) = eval(t, &mut step);
+                        if !armijo(t, v) || v >= step.value {
+                            break;
+                        }
+                        step = Step { t, coeffs: x, value: v, ..step };
+                    }
+                    return step;
+                }
+                // backtracking phase
+                while step.evaluations < max_evals {
+                    t *= shrink;
+                    let (x, v) = eval(t, &mut step);
+                    if armijo(t, v) {
+                        return Step { t, coeffs: x, value: v, success: true, ..step };
+                    }
+                }
+                step
+            }
+            LineSearch::StrongWolfe { c1, c2, max_evals } => {
+                let armijo = |t: f64, v: f64| v <= f0 + c1 * t * slope0;
+                let curvature = |s: f64| s.abs() <= -c2 * slope0;
+
+                // bracketing phase
+                let (mut lo, mut hi, mut v_lo, mut v_hi, mut s_lo);
+                let (mut t_prev, mut v_prev, mut s_prev) = (0.0, f0, slope0);
+                let mut t = t0;
+                loop {
+                    let (x, v) = eval(t, &mut step);
+                    if !armijo(t, v) || (t_prev > 0.0 && v >= v_prev) {
+                        (lo, hi, v_lo, v_hi, s_lo) = (t_prev, t, v_prev, v, s_prev);
+                        break;
+                    }
+                    step.grad_evaluations += 1;
+                    let g = grad(&x);
+                    let s = dot(&to_array(&g), &d);
+                    // a point satisfying sufficient decrease, kept as fallback
+                    step = Step { t, coeffs: x, value: v, grad: Some(g), ..step };
+                    if curvature(s) {
+                        return Step { success: true, ..step };
+                    }
+                    if s >= 0.0 {
+                        (lo, hi, v_lo, v_hi, s_lo) = (t, t_prev, v, v_prev, s);
+                        break;
+                    }
+                    if step.evaluations >= max_evals {
+                        return step;
+                    }
+                    (t_prev, v_prev, s_prev) = (t, v, s);
+                    t *= 2.0;
+                }
+
+                // zoom phase, the minimizer of the quadratic through
+                // (lo, v_lo, s_lo) and (hi, v_hi), kept away from the ends
+                while step.evaluations < max_evals {
+                    let w = hi - lo;
+                    let denom = 2.0 * (v_hi - v_lo - s_lo * w);
+                    let mut t = if denom > 0.0 { lo - s_lo * w * w / denom } else { lo + 0.5 * w };
+                    let (a, b) = (lo.min(hi), lo.max(hi));
+                    if !(t > a + 0.1 * (b - a) && t < b - 0.1 * (b - a)) {
+                        t = lo + 0.5 * w;
+                    }
+
+                    // the gradient is only needed once the decrease is sufficient
+                    let (x, v) = eval(t, &mut step);
+                    if !armijo(t, v) || v >= v_lo {
+                        (hi, v_hi) = (t, v);
+                        continue;
+                    }
+                    step.grad_evaluations += 1;
+                    let g = grad(&x);
+                    let s = dot(&to_array(&g), &d);
+                    step = Step { t, coeffs: x, value: v, grad: Some(g), ..step };
+                    if curvature(s) {
+                        return Step { success: true, ..step };
+                    }
+                    if s * (hi - lo) >= 0.0 {
+                        (hi, v_hi) = (lo, v_lo);
+                    }
+                    (lo, v_lo, s_lo) = (t, v, s);
+                }
+                step
+            }
+        }
+    }
+}
+
+pub(crate) fn dot(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    // f(c) = Σ (k+1)·c_k², minimum at 0
+    fn f(c: &Params) -> f64 {
+        to_array(c).iter().enumerate().map(|(k, v)| (k + 1) as f64 * v * v).sum()
+    }
+
+    fn grad(c: &Params) -> Params {
+        let c = to_array(c);
+        from_array(std::array::from_fn(|k| 2.0 * (k + 1) as f64 * c[k]))
+    }
+
+    // value, gradient and steepest descent direction at (1, 1, 0, …)
+    fn start() -> (Params, f64, Params, Params) {
+        let c = from_array(std::array::from_fn(|k| if k < 2 { 1.0 } else { 0.0 }));
+        let g = grad(&c);
+        (c, f(&c), g, from_array(to_array(&g).map(|v| -v)))
+    }
+
+    #[test]
+    fn armijo_expands_a_short_trial_step() {
+        let (c, f0, g0, d) = start();
+        let step = LineSearch::armijo().search(f, grad, &c, &d, f0, &g0, 1e-3);
+        assert!(step.success);
+        assert!(step.t > 1e-3);
+        let (c, d) = (to_array(&c), to_array(&d));
+        assert!(step.value < f(&from_array(std::array::from_fn(|k| c[k] + 1e-3 * d[k]))));
+        assert_eq!(step.grad_evaluations, 0);
+    }
+
+    #[test]
+    fn armijo_backtracks_a_long_trial_step() {
+        let (c, f0, g0, d) = start();
+        let step = LineSearch::armijo().search(f, grad, &c, &d, f0, &g0, 100.0);
+        assert!(step.success);
+        assert!(step.t < 100.0);
+        let slope = dot(&to_array(&g0), &to_array(&d));
+        assert!(step.value <= f0 + 1e-4 * step.t * slope);
+    }
+
+    #[test]
+    fn wolfe_satisfies_both_conditions() {
+        let (c, f0, g0, d) = start();
+        let slope = dot(&to_array(&g0), &to_array(&d));
+        for t0 in [1e-4, 1.0, 100.0] {
+            let step = LineSearch::strong_wolfe().search(f, grad, &c, &d, f0, &g0, t0);
+            assert!(step.success, "t0 = {}", t0);
+            assert!(step.value <= f0 + 1e-4 * step.t * slope);
+            let s = dot(&to_array(step.grad.as_ref().unwrap()), &to_array(&d));
+            assert!(s.abs() <= 0.9 * slope.abs());
+        }
+    }
+
+    #[test]
+    fn zoom_skips_the_gradient_at_rejected_points() {
+        let (c, f0, g0, d) = start();
+        let calls = Cell::new(0);
+        let counted = |c: &Params| {
+            calls.set(calls.get() + 1);
+            grad(c)
+        };
+        // c2 small enough to need a zoom after overshooting
+        let ls = LineSearch::StrongWolfe { c1: 1e-4, c2: 0.1, max_evals: 40 };
+        let step = ls.search(f, counted, &c, &d, f0, &g0, 100.0);
+        assert!(step.success);
+        assert_eq!(step.grad_evaluations, calls.get());
+        assert!(step.grad_evaluations < step.evaluations);
+    }
+}
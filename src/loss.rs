@@ -0,0 +1,504 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use slut::{complex::c64, dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+use crate::autodiff::Var;
+use crate::dual::Dual;
+
+/// Scalars a loss can be evaluated on: plain values, tape variables for
+/// reverse mode and duals for forward mode, so both differentiate the loss
+/// itself.
+pub trait Real:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self>
+{
+    fn raw(&self) -> f64;
+
+    /// `v` as a constant of the same kind as `self`.
+    fn lift(&self, v: f64) -> Self;
+
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn tanh(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+}
+
+impl Real for f64 {
+    fn raw(&self) -> f64 {
+        *self
+    }
+
+    fn lift(&self, v: f64) -> Self {
+        v
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn tanh(self) -> Self {
+        f64::tanh(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+
+// Mean of `f` over a non-empty slice.
+fn mean<T: Real>(values: &[T], f: impl Fn(T) -> T) -> T {
+    let sum = values.iter().map(|&v| f(v)).reduce(|a, b| a + b).expect("mean of no values");
+    sum / sum.lift(values.len() as f64)
+}
+
+/// Pointwise loss of a residual `r = prediction - target`.
+///
+/// The training objective is `total` over all residuals, the mean of `value`
+/// unless a loss needs to see every sample at once (the smooth max).
+pub trait Loss: Send {
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless>;
+
+    /// `value` on a tape variable, for reverse-mode differentiation.
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t>;
+
+    /// `value` on a dual number, for forward-mode differentiation.
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless>;
+
+    /// `value` on a complex number, for complex-step differentiation.
+    fn value_complex(&self, r: c64) -> c64;
+
+    /// `∂value/∂r`, in closed form.
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless>;
+
+    fn total(&self, residuals: &[f64]) -> f64 {
+        residuals.iter().map(|&r| self.value(dless!(r)).raw()).sum::<f64>() / residuals.len() as f64
+    }
+
+    /// `total` on tape variables. `residuals` must not be empty.
+    fn total_var<'t>(&self, residuals: &[Var<'t>]) -> Var<'t> {
+        mean(residuals, |r| self.value_var(r))
+    }
+
+    /// `total` on dual numbers. `residuals` must not be empty.
+    fn total_dual(&self, residuals: &[Dual<Dimensionless>]) -> Dual<Dimensionless> {
+        mean(residuals, |r| self.value_dual(r))
+    }
+
+    /// `total` on complex numbers. `residuals` must not be empty.
+    fn total_complex(&self, residuals: &[c64]) -> c64 {
+        mean(residuals, |r| self.value_complex(r))
+    }
+
+    /// `∂total/∂r_i` for every residual.
+    fn total_grad(&self, residuals: &[f64]) -> Vec<f64> {
+        let m = residuals.len() as f64;
+        residuals.iter().map(|&r| self.deriv(dless!(r)).raw() / m).collect()
+    }
+
+    /// Power of the target dimension in the loss value, 2 for `[y]²`.
+    /// Losses of degree 0 only make sense on dimensionless targets.
+    fn degree(&self) -> i32 {
+        2
+    }
+
+    fn name(&self) -> &'static str;
+}
+
+/// Look up a loss with its default settings.
+pub fn by_name(name: &str) -> Option<Box<dyn Loss>> {
+    let l: Box<dyn Loss> = match name {
+        "mse" => Box::new(Mse),
+        "mae" => Box::new(Mae),
+        "huber" => Box::new(Huber { delta: 0.1 }),
+        "logcosh" => Box::new(LogCosh),
+        "quantile" => Box::new(Quantile { tau: 0.5 }),
+        "maxerr" => Box::new(SmoothMax { beta: 100.0, eps: 1e-6 }),
+        _ => return None,
+    };
+    Some(l)
+}
+
+/// Squared error `r²`.
+pub struct Mse;
+
+impl Mse {
+    fn at<T: Real>(&self, r: T) -> T {
+        r * r
+    }
+}
+
+impl Loss for Mse {
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.at(r.raw()))
+    }
+
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t> {
+        self.at(r)
+    }
+
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless> {
+        self.at(r)
+    }
+
+    fn value_complex(&self, r: c64) -> c64 {
+        self.at(r)
+    }
+
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        r * dless!(2.0)
+    }
+
+    fn name(&self) -> &'static str {
+        "mse"
+    }
+}
+
+/// Absolute error `|r|`, subgradient 0 at the origin.
+pub struct Mae;
+
+impl Mae {
+    fn at<T: Real>(&self, r: T) -> T {
+        r.abs()
+    }
+}
+
+impl Loss for Mae {
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.at(r.raw()))
+    }
+
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t> {
+        self.at(r)
+    }
+
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless> {
+        self.at(r)
+    }
+
+    fn value_complex(&self, r: c64) -> c64 {
+        self.at(r)
+    }
+
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        let r = r.raw();
+        dless!(if r > 0.0 { 1.0 } else if r < 0.0 { -1.0 } else { 0.0 })
+    }
+
+    fn degree(&self) -> i32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "mae"
+    }
+}
+
+/// Quadratic within `delta` of zero, linear outside.
+pub struct Huber {
+    pub delta: f64,
+}
+
+impl Huber {
+    fn at<T: Real>(&self, r: T) -> T {
+        let a = r.abs();
+        if a.raw() <= self.delta {
+            a * a * a.lift(0.5)
+        } else {
+            (a - a.lift(0.5 * self.delta)) * a.lift(self.delta)
+        }
+    }
+}
+
+impl Loss for Huber {
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.at(r.raw()))
+    }
+
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t> {
+        self.at(r)
+    }
+
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless> {
+        self.at(r)
+    }
+
+    fn value_complex(&self, r: c64) -> c64 {
+        self.at(r)
+    }
+
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(r.raw().clamp(-self.delta, self.delta))
+    }
+
+    fn name(&self) -> &'static str {
+        "huber"
+    }
+}
+
+/// `ln cosh r`, smooth everywhere and linear in the tails.
+pub struct LogCosh;
+
+impl LogCosh {
+    fn at<T: Real>(&self, r: T) -> T {
+        // |r| + ln(1 + e^{-2|r|}) - ln 2 does not overflow for large |r|
+        let a = r.abs();
+        a + ((a * a.lift(-2.0)).exp() + a.lift(1.0)).ln() - a.lift(std::f64::consts::LN_2)
+    }
+}
+
+impl Loss for LogCosh {
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.at(r.raw()))
+    }
+
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t> {
+        self.at(r)
+    }
+
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless> {
+        self.at(r)
+    }
+
+    fn value_complex(&self, r: c64) -> c64 {
+        self.at(r)
+    }
+
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(r.raw().tanh())
+    }
+
+    fn degree(&self) -> i32 {
+        0
+    }
+
+    fn name(&self) -> &'static str {
+        "logcosh"
+    }
+}
+
+/// Pinball loss for the `tau` quantile: under-predictions cost `tau`,
+/// over-predictions `1 - tau`.
+pub struct Quantile {
+    pub tau: f64,
+}
+
+impl Quantile {
+    fn at<T: Real>(&self, r: T) -> T {
+        if r.raw() < 0.0 { -r * r.lift(self.tau) } else { r * r.lift(1.0 - self.tau) }
+    }
+}
+
+impl Loss for Quantile {
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.at(r.raw()))
+    }
+
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t> {
+        self.at(r)
+    }
+
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless> {
+        self.at(r)
+    }
+
+    fn value_complex(&self, r: c64) -> c64 {
+        self.at(r)
+    }
+
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(if r.raw() < 0.0 { -self.tau } else { 1.0 - self.tau })
+    }
+
+    fn degree(&self) -> i32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "quantile"
+    }
+}
+
+/// Smooth surrogate of the maximum absolute error,
+/// `1/β · ln(1/M Σ exp(β·|r_i|))` with `|r| ≈ √(r² + ε²)`.
+/// Approaches the true max error as `beta` grows.
+pub struct SmoothMax {
+    pub beta: f64,
+    pub eps: f64,
+}
+
+impl SmoothMax {
+    fn abs(&self, r: f64) -> f64 {
+        (r * r + self.eps * self.eps).sqrt()
+    }
+
+    fn at<T: Real>(&self, r: T) -> T {
+        (r * r + r.lift(self.eps * self.eps)).sqrt()
+    }
+
+    fn total_of<T: Real>(&self, residuals: &[T]) -> T {
+        let a: Vec<T> = residuals.iter().map(|&r| self.at(r)).collect();
+        // shift by the max to keep the exponentials finite, the shift
+        // cancels so it is taken as a constant
+        let top = a.iter().map(|v| v.raw()).fold(f64::NEG_INFINITY, f64::max);
+        let beta = a[0].lift(self.beta);
+        let s = mean(&a, |v| ((v - v.lift(top)) * beta).exp());
+        s.lift(top) + s.ln() / beta
+    }
+}
+
+impl Loss for SmoothMax {
+    // pointwise it is just the smoothed absolute error
+    fn value(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.abs(r.raw()))
+    }
+
+    fn value_var<'t>(&self, r: Var<'t>) -> Var<'t> {
+        self.at(r)
+    }
+
+    fn value_dual(&self, r: Dual<Dimensionless>) -> Dual<Dimensionless> {
+        self.at(r)
+    }
+
+    fn value_complex(&self, r: c64) -> c64 {
+        self.at(r)
+    }
+
+    fn deriv(&self, r: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        let r = r.raw();
+        dless!(r / self.abs(r))
+    }
+
+    fn total(&self, residuals: &[f64]) -> f64 {
+        self.total_of(residuals)
+    }
+
+    fn total_var<'t>(&self, residuals: &[Var<'t>]) -> Var<'t> {
+        self.total_of(residuals)
+    }
+
+    fn total_dual(&self, residuals: &[Dual<Dimensionless>]) -> Dual<Dimensionless> {
+        self.total_of(residuals)
+    }
+
+    fn total_complex(&self, residuals: &[c64]) -> c64 {
+        self.total_of(residuals)
+    }
+
+    fn total_grad(&self, residuals: &[f64]) -> Vec<f64> {
+        let a: Vec<f64> = residuals.iter().map(|&r| self.abs(r)).collect();
+        let top = a.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let w: Vec<f64> = a.iter().map(|&v| (self.beta * (v - top)).exp()).collect();
+        let sum: f64 = w.iter().sum();
+        // softmax weights times d|r|/dr
+        residuals.iter().zip(w).map(|(&r, w)| w / sum * r / self.abs(r)).collect()
+    }
+
+    fn degree(&self) -> i32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "maxerr"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autodiff::Tape;
+
+    const NAMES: [&str; 6] = ["mse", "mae", "huber", "logcosh", "quantile", "maxerr"];
+
+    #[test]
+    fn total_grad_matches_central_differences() {
+        let r = [0.3, -1.2, 0.05, 2.0, -0.4];
+        for name in NAMES {
+            let loss = by_name(name).unwrap();
+            let g = loss.total_grad(&r);
+            for i in 0..r.len() {
+                let h = 1e-6;
+                let (mut up, mut down) = (r, r);
+                up[i] += h;
+                down[i] -= h;
+                let fd = (loss.total(&up) - loss.total(&down)) / (2.0 * h);
+                assert!((g[i] - fd).abs() < 1e-6, "{} at r{}: {} vs {}", name, i, g[i], fd);
+            }
+        }
+    }
+
+    #[test]
+    fn taped_total_matches_total_grad() {
+        let r = [0.3, -1.2, 0.05, 2.0, -0.4];
+        for name in NAMES {
+            let loss = by_name(name).unwrap();
+            let tape = Tape::new();
+            let vars: Vec<Var> = r.iter().map(|&v| tape.var(dless!(v))).collect();
+            let total = loss.total_var(&vars);
+            assert!((total.raw() - loss.total(&r)).abs() < 1e-12, "{}", name);
+            let grads = total.backward();
+            for (v, want) in vars.iter().zip(loss.total_grad(&r)) {
+                let got = grads.wrt(*v).raw();
+                assert!((got - want).abs() < 1e-12, "{}: {} vs {}", name, got, want);
+            }
+        }
+    }
+
+    #[test]
+    fn dual_total_matches_total_grad() {
+        let r = [0.3, -1.2, 0.05, 2.0, -0.4];
+        for name in NAMES {
+            let loss = by_name(name).unwrap();
+            let want = loss.total_grad(&r);
+            for (i, want) in want.iter().enumerate() {
+                let d: Vec<Dual<Dimensionless>> = r
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &v)| if i == j { Dual::variable(dless!(v)) } else { Dual::constant(dless!(v)) })
+                    .collect();
+                let got = loss.total_dual(&d).eps.raw();
+                assert!((got - want).abs() < 1e-12, "{} at r{}: {} vs {}", name, i, got, want);
+            }
+        }
+    }
+
+    #[test]
+    fn huber_joins_continuously() {
+        let h = Huber { delta: 0.1 };
+        let at = |r: f64| h.value(dless!(r)).raw();
+        assert!((at(0.1 - 1e-12) - at(0.1 + 1e-12)).abs() < 1e-12);
+        assert_eq!(at(-2.0), 0.1 * (2.0 - 0.05));
+    }
+
+    #[test]
+    fn log_cosh_does_not_overflow() {
+        let l = LogCosh.value(dless!(1e3)).raw();
+        assert!((l - (1e3 - std::f64::consts::LN_2)).abs() < 1e-9);
+        assert!(LogCosh.value(dless!(0.0)).raw().abs() < 1e-15);
+    }
+
+    #[test]
+    fn quantile_weighs_the_sides() {
+        let q = Quantile { tau: 0.9 };
+        // under-predicting by 1 costs τ, over-predicting 1 - τ
+        assert!((q.value(dless!(-1.0)).raw() - 0.9).abs() < 1e-15);
+        assert!((q.value(dless!(1.0)).raw() - 0.1).abs() < 1e-15);
+    }
+
+    #[test]
+    fn smooth_max_approaches_the_max() {
+        let r = [0.1, -0.5, 0.3];
+        let loose = SmoothMax { beta: 10.0, eps: 1e-9 }.total(&r);
+        let tight = SmoothMax { beta: 1e4, eps: 1e-9 }.total(&r);
+        assert!(loose < tight && tight <= 0.5);
+        assert!(0.5 - tight < 1e-3);
+    }
+}
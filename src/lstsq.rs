@@ -0,0 +1,300 @@
+use crate::data::Dataset;
+use crate::model::Model;
+use crate::{from_array, N, Params};
+
+/// How to solve the least-squares system.
+#[derive(Clone, Copy, Debug)]
+pub enum Method {
+    /// Householder QR, fast and accurate for full-rank designs.
+    Qr,
+    /// One-sided Jacobi SVD, handles rank-deficient designs and reports the
+    /// condition number.
+    Svd,
+}
+
+/// Result of a least-squares fit.
+#[derive(Clone, Debug)]
+pub struct LstsqFit {
+    pub coeffs: Params,
+    /// Sum of squared residuals.
+    pub rss: f64,
+    /// `rss / points`, directly comparable to the trainer's MSE.
+    pub mse: f64,
+    pub max_abs_err: f64,
+    /// Coefficient of determination.
+    pub r2: f64,
+    pub rank: usize,
+    /// Ratio of extreme singular values, only known for `Method::Svd`.
+    pub cond: Option<f64>,
+}
+
+// relative cutoff under which a pivot or singular value counts as zero
+const RCOND: f64 = 1e-12;
+
+/// Fit a model that is linear in its parameters by least squares over the
+/// samples of `data`.
+///
+/// Returns `None` if the model does not expose `features`, if there are no
+/// samples, or if the design has rank zero and so determines nothing.
+pub fn fit<M>(model: &M, data: &Dataset, method: Method) -> Option<LstsqFit>
+where
+    M: Model + ?Sized,
+{
+    let n = model.param_count();
+    let num_points = data.len();
+    if num_points == 0 {
+        return None;
+    }
+
+    // design matrix and right-hand side
+    let mut a = Vec::with_capacity(num_points);
+    for &x in &data.x {
+        a.push(crate::to_array(&model.features(x)?));
+    }
+    let b = data.y.clone();
+
+    let (c, rank, cond) = match method {
+        Method::Qr => {
+            let (c, rank) = solve_qr(a.clone(), b.clone(), n);
+            (c, rank, None)
+        }
+        Method::Svd => {
+            let (c, rank, cond) = solve_svd(a.clone(), &b, n);
+            (c, rank, Some(cond))
+        }
+    };
+    if rank == 0 {
+        return None;
+    }
+
+    // residual statistics
+    let mean = b.iter().sum::<f64>() / num_points as f64;
+    let mut rss = 0.0;
+    let mut tss = 0.0;
+    let mut max_abs_err = 0.0f64;
+    for (row, t) in a.iter().zip(&b) {
+        let y: f64 = (0..n).map(|k| row[k] * c[k]).sum();
+        let r = y - t;
+        rss += r * r;
+        tss += (t - mean) * (t - mean);
+        max_abs_err = max_abs_err.max(r.abs());
+    }
+
+    Some(LstsqFit {
+        coeffs: from_array(c),
+        rss,
+        mse: rss / num_points as f64,
+        max_abs_err,
+        r2: if tss > 0.0 { 1.0 - rss / tss } else { 1.0 },
+        rank,
+        cond,
+    })
+}
+
+// The solvers work on rows of plain arrays rather than slut matrices: the
+// number of samples is only known at run time, while slut matrices fix both
+// dimensions at compile time.
+
+// Householder QR on the first n columns, then back substitution.
+pub(crate) fn solve_qr(mut a: Vec<[f64; N]>, mut b: Vec<f64>, n: usize) -> ([f64; N], usize) {
+    let m = a.len();
+    let mut diag = [0.0f64; N];
+
+    for j in 0..n.min(m) {
+        let norm = (j..m).map(|i| a[i][j] * a[i][j]).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        let alpha = if a[j][j] > 0.0 { -norm } else { norm };
+
+        // v = x - alpha·e₁, stored in place below the diagonal
+        a[j][j] -= alpha;
+        let vnorm2 = (j..m).map(|i| a[i][j] * a[i][j]).sum::<f64>();
+        diag[j] = alpha;
+        if vnorm2 == 0.0 {
+            continue;
+        }
+
+        for k in j + 1..n {
+            let s = (j..m).map(|i| a[i][j] * a[i][k]).sum::<f64>() * 2.0 / vnorm2;
+            for row in a[j..m].iter_mut() {
+                row[k] -= s * row[j];
+            }
+        }
+        let s = (j..m).map(|i| a[i][j] * b[i]).sum::<f64>() * 2.0 / vnorm2;
+        for (bi, row) in b[j..m].iter_mut().zip(&a[j..m]) {
+            *bi -= s * row[j];
+        }
+    }
+
+    let max_diag = diag.iter().fold(0.0f64, |acc, d| acc.max(d.abs()));
+    let mut rank = 0;
+    let mut c = [0.0f64; N];
+    for j in (0..n.min(m)).rev() {
+        if diag[j].abs() <= RCOND * max_diag {
+            // dependent column, leave its coefficient at zero
+            continue;
+        }
+        rank += 1;
+        let s = b[j] - a[j][j + 1..n].iter().zip(&c[j + 1..n]).map(|(a, c)| a * c).sum::<f64>();
+        c[j] = s / diag[j];
+    }
+    (c, rank)
+}
+
+// One-sided Jacobi SVD: orthogonalize the columns of A, then apply the
+// pseudo-inverse. Returns the solution, the numerical rank and the
+// condition number.
+fn solve_svd(mut u: Vec<[f64; N]>, b: &[f64], n: usize) -> ([f64; N], usize, f64) {
+    let m = u.len();
+    let mut v = [[0.0f64; N]; N];
+    for (k, row) in v.iter_mut().enumerate() {
+        row[k] = 1.0;
+    }
+
+    for _sweep in 0..60 {
+        let mut rotated = false;
+        for p in 0..n {
+            for q in p + 1..n {
+                let alpha: f64 = (0..m).map(|i| u[i][p] * u[i][p]).sum();
+                let beta: f64 = (0..m).map(|i| u[i][q] * u[i][q]).sum();
+                let gamma: f64 = (0..m).map(|i| u[i][p] * u[i][q]).sum();
+                if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() {
+                    continue;
+                }
+                rotated = true;
+
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let cs = 1.0 / (1.0 + t * t).sqrt();
+                let sn = cs * t;
+                for row in u.iter_mut() {
+                    let (up, uq) = (row[p], row[q]);
+                    row[p] = cs * up - sn * uq;
+                    row[q] = sn * up + cs * uq;
+                }
+                for row in v.iter_mut() {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = cs * vp - sn * vq;
+                    row[q] = sn * vp + cs * vq;
+                }
+            }
+        }
+        if !rotated {
+            break;
+        }
+    }
+
+    let mut sigma = [0.0f64; N];
+    for (j, s) in sigma.iter_mut().enumerate().take(n) {
+        *s = (0..m).map(|i| u[i][j] * u[i][j]).sum::<f64>().sqrt();
+    }
+    let s_max = sigma.iter().take(n).fold(0.0f64, |acc, s| acc.max(*s));
+
+    let mut c = [0.0f64; N];
+    let mut rank = 0;
+    let mut s_min = f64::INFINITY;
+    for (j, &s) in sigma.iter().enumerate().take(n) {
+        if s <= RCOND * s_max {
+            continue;
+        }
+        rank += 1;
+        s_min = s_min.min(s);
+        // (u_j · b) / σ_j², since the column of U still carries σ_j
+        let ub: f64 = (0..m).map(|i| u[i][j] * b[i]).sum();
+        let w = ub / (s * s);
+        for (ck, vk) in c.iter_mut().zip(&v).take(n) {
+            *ck += w * vk[j];
+        }
+    }
+    (c, rank, s_max / s_min)
+}
+
+#[cfg(test)]
+mod tests {
+    use slut::{dimension::Dimensionless, dot, tensor::Scalar};
+
+    use super::*;
+    use crate::model::Polynomial;
+    use crate::to_array;
+
+    const METHODS: [Method; 2] = [Method::Qr, Method::Svd];
+
+    #[test]
+    fn recovers_an_exact_cubic() {
+        let data = Dataset::grid(|x| 1.0 - 2.0 * x + 0.5 * x * x * x, -1.0, 0.1, 21);
+        for method in METHODS {
+            let fit = fit(&Polynomial::new(4), &data, method).unwrap();
+            let c = to_array(&fit.coeffs);
+            for (k, want) in [1.0, -2.0, 0.0, 0.5].into_iter().enumerate() {
+                assert!((c[k] - want).abs() < 1e-10, "{:?}: c{} = {}", method, k, c[k]);
+            }
+            assert_eq!(fit.rank, 4);
+            assert!(fit.rss < 1e-20 && (fit.r2 - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn residuals_of_a_line_through_a_parabola() {
+        // best line through (-1, 1), (0, 0), (1, 1) is y = ⅔
+        let data = Dataset::new(vec![-1.0, 0.0, 1.0], vec![1.0, 0.0, 1.0]);
+        for method in METHODS {
+            let fit = fit(&Polynomial::new(2), &data, method).unwrap();
+            let c = to_array(&fit.coeffs);
+            assert!((c[0] - 2.0 / 3.0).abs() < 1e-12 && c[1].abs() < 1e-12, "{:?}", method);
+            assert!((fit.rss - 2.0 / 3.0).abs() < 1e-12);
+            assert!((fit.max_abs_err - 2.0 / 3.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn rank_deficient_designs() {
+        // two points cannot determine a cubic
+        let data = Dataset::new(vec![0.0, 1.0], vec![1.0, 3.0]);
+        let svd = fit(&Polynomial::new(4), &data, Method::Svd).unwrap();
+        assert_eq!(svd.rank, 2);
+        assert!(svd.rss < 1e-20);
+        assert!(fit(&Polynomial::new(4), &data, Method::Qr).unwrap().rss < 1e-20);
+    }
+
+    #[test]
+    fn svd_reports_the_condition_number() {
+        // columns 1 and x over x = ±1 are orthogonal with equal norms
+        let data = Dataset::new(vec![-1.0, 1.0], vec![0.0, 2.0]);
+        let fit = fit(&Polynomial::new(2), &data, Method::Svd).unwrap();
+        assert!((fit.cond.unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    // y = c·x, which samples at x = 0 say nothing about
+    struct Slope;
+
+    impl Model for Slope {
+        fn name(&self) -> &'static str {
+            "slope"
+        }
+
+        fn param_count(&self) -> usize {
+            1
+        }
+
+        fn forward(&self, params: &Params, x: f64) -> Scalar<f64, Dimensionless> {
+            dot!(*params, self.features(x).unwrap())
+        }
+
+        fn features(&self, x: f64) -> Option<Params> {
+            let mut f = [0.0; N];
+            f[0] = x;
+            Some(from_array(f))
+        }
+    }
+
+    #[test]
+    fn nothing_to_fit() {
+        let origin = Dataset::new(vec![0.0; 3], vec![1.0; 3]);
+        for method in METHODS {
+            assert!(fit(&Polynomial::new(2), &Dataset::default(), method).is_none());
+            assert!(fit(&Slope, &origin, method).is_none(), "{:?}", method);
+            assert!(fit(&Slope, &Dataset::new(vec![0.0, 2.0], vec![1.0, 1.0]), method).is_some());
+        }
+    }
+}
@@ -1,195 +1,347 @@
 #![feature(generic_const_exprs)]
 #![feature(trivial_bounds)]
-#![feature(generic_arg_infer)]
 #![allow(incomplete_features)]
 
+pub mod autodiff;
+pub mod complex;
+pub mod csv;
+pub mod curriculum;
+pub mod data;
+pub mod derivative_free;
+pub mod dims;
+pub mod dual;
+pub mod grad;
+pub mod gradcheck;
+pub mod linesearch;
+pub mod loss;
+pub mod lstsq;
+pub mod model;
+pub mod multistart;
+pub mod newton;
+pub mod npy;
+pub mod optim;
+pub mod pi;
 pub mod plot;
-use crate::plot::{plot_comparison,loss_curve};
-
-use slut::{dimension::{self, Dimensionless, *}, dless, dot, tensor::*,units::{self, Unitless}};
+pub mod regularize;
+pub mod remez;
+pub mod schedule;
+pub mod train;
+use std::error::Error;
+
+use crate::curriculum::Curriculum;
+use crate::data::{Batcher, Dataset, Split};
+use crate::dims::{DimError, Dimensional, Sq, TrainingDims};
+use crate::model::{Model, Polynomial};
+use crate::multistart::Init;
+use crate::optim::Optimizer;
+use crate::plot::{plot_comparison,plot_data,loss_curve};
+use crate::schedule::LrScheduler;
+use crate::train::{EarlyStopping, GradMode, Trainer};
+
+use slut::dimension::{Amount, Current, Dimensionless, Length, LuminousIntensity, Mass, SquareDimension, Temperature, Time};
+use slut::si::{Acceleration, Force, Velocity};
+use slut::tensor::*;
 
 const N: usize = 10;
-static mut enabled:usize = 3;
-static mut threshold:f64 = -5e-5;
 
-fn fac(n: i32) -> i32 {
-    let mut r = 1;
-    for i in 1..n+1 {
-        r*= i;
-    }
-    r
+/// The parameter vector, also used for gradients. Generic items and traits
+/// name it through this alias, where a bare `N` would be an unconstrained
+/// constant.
+pub type Params = Vector<f64,Dimensionless,N>;
+
+fn to_array(v: &Params) -> [f64; N] {
+    std::array::from_fn(|k| v.get_at(0, k, 0).raw())
 }
 
-fn infer(coeffs: Vector<f64,Dimensionless,N>, x: f64) -> Scalar<f64,Dimensionless> {
-    // create a vector like [1, x¹, x², ... xⁿ⁻¹]
-    let mut input_data = [0.0f64; N];
-    let r = unsafe{enabled};
-    for i in 0..r {
-        input_data[i] = x.powi(i as i32);
-    }
+fn from_array(a: [f64; N]) -> Params {
+    Params::default(a)
+}
 
-    let inputs = Vector::<f64,Dimensionless,N>::default(input_data);
+// A target known as a function, which Remez and the printouts compare against
+type Target = fn(f64) -> f64;
 
-    let y = dot!(coeffs, inputs);
-    y
+fn target(a: f64) -> f64 {
+    a.cos()
 }
 
 fn main() {
-    println!("{}", fac(3));
-
-    let target = |a: f64| -> f64 {a.cos()};
-
-    let h = dless!(5e-15);
-    let mut glr = dless!(1e-4);
-
-
-    let loss = |r: Scalar<f64,Dimensionless>, t: Scalar<f64,Dimensionless>, coeffs: &Vector<f64,Dimensionless,N>| {
-        let d = ((r-t)*(r-t)).mag();
-        d
-    };
-
-    let mut coeffs = Vector::<f64,Dimensionless,N>::zero();
-    println!("{}", coeffs);
-
-    let step = dless!(0.01);
-    let max = dless!(5.0);
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
 
-    // Compute MSE over all training points
-    let mse = |c: &Vector<f64,Dimensionless,N>| {
-        let mut total_loss = Scalar::<f64,Dimensionless>::zero();
-        let num_points = (max / step).raw() as usize;
-        
-        for i in 0..num_points {
-            let x = i as f64 * step.raw();
-            total_loss = total_loss + loss(infer(c.clone(), x), dless!(target(x)), c);
-        }
-        total_loss / dless!(num_points as f64)
-    };
+fn run() -> Result<(), Box<dyn Error>> {
+    // `--pi data.csv` fits a scaling law of the last column over the
+    // dimensionless groups of the others instead of training
+    if std::env::args().nth(1).as_deref() == Some("--pi") {
+        let path = std::env::args().nth(2).expect("Usage: --pi <data.csv>");
+        pi_fit(&path);
+        return Ok(());
+    }
 
-    // Gradient using numerical differentiation of MSE
-    let grad = |c: &Vector<f64,Dimensionless,N>, k: usize| {
-        let mut c_plus = c.clone();
-        c_plus.add_at(0, k, 0, h);
-        (mse(&c_plus) - mse(c)) / h
+    // data file as sixth argument, otherwise sample the target on [0, 5);
+    // the target function is only known in the second case
+    let (data, known): (Dataset, Option<Target>) = match std::env::args().nth(6) {
+        Some(path) => (Dataset::load(&path).expect("Failed to load dataset"), None),
+        None => (Dataset::grid(target, 0.0, 0.01, 500), Some(target)),
     };
 
-    let epochs =  5000;
-
-    println!("Step: {}, Max: {}, Epochs: {}", step, max, epochs);
-    println!("Target: {}", target(1.0));
+    // the trainer is typed by the dimension of the targets, so only the
+    // dimensions listed here can be fitted
+    let d = data.y_dim;
+    if d == Dimensionless::DIM {
+        fit::<Dimensionless>(data, known)?;
+    } else if d == Length::DIM {
+        fit::<Length>(data, known)?;
+    } else if d == Mass::DIM {
+        fit::<Mass>(data, known)?;
+    } else if d == Time::DIM {
+        fit::<Time>(data, known)?;
+    } else if d == Current::DIM {
+        fit::<Current>(data, known)?;
+    } else if d == Temperature::DIM {
+        fit::<Temperature>(data, known)?;
+    } else if d == Amount::DIM {
+        fit::<Amount>(data, known)?;
+    } else if d == LuminousIntensity::DIM {
+        fit::<LuminousIntensity>(data, known)?;
+    } else if d == Velocity::DIM {
+        fit::<Velocity>(data, known)?;
+    } else if d == Acceleration::DIM {
+        fit::<Acceleration>(data, known)?;
+    } else if d == Force::DIM {
+        fit::<Force>(data, known)?;
+    } else {
+        return Err(format!("Unsupported target dimension: {}", d).into());
+    }
+    Ok(())
+}
 
-    let starting_loss = mse(&coeffs);
-    println!("Starting loss: {}", starting_loss);
+fn pi_fit(path: &str) {
+    let pi::Table { inputs, target, rows, y } = pi::read_csv(path).expect("Failed to load dataset");
+    let analysis = pi::PiAnalysis::new(inputs, target).unwrap_or_else(|e| panic!("{}", e));
 
-    println!("Coeffs: {}", coeffs);
+    println!("Samples: {}, Target: {} in {}", rows.len(), analysis.target.name, analysis.target.dim);
+    for (v, e) in analysis.inputs.iter().zip(&analysis.target_exponents) {
+        println!("  {} in {}, exponent {} in the target scale", v.name, v.dim, e);
+    }
+    for i in 0..analysis.groups.len() {
+        println!("π{} = {}", i + 1, analysis.group_name(i));
+    }
 
-    let mut l = Scalar::zero();
-    let mut losses = Vec::<f64>::new();
-    let max_gnorm = dless!(10.0);
+    match analysis.fit_power_law(&rows, &y) {
+        Some(law) => {
+            let err = rows.iter().zip(&y).map(|(r, y)| ((law.predict(&analysis, r) - y) / y).abs()).fold(0.0, f64::max);
+            println!("Scaling law: π_y = {} with exponents {:?}", law.coeff, law.exponents);
+            println!("Max relative error: {:+e}", err);
+        }
+        None => println!("Not enough positive samples to fit a scaling law"),
+    }
+}
 
-    let mut last_conv:i32 = -300;
+// The loss values are in `[Y]^degree` for the degree of the selected loss.
+fn fit<Y>(data: Dataset, target: Option<Target>) -> Result<(), DimError>
+where
+    Y: Dimensional + SquareDimension + TrainingDims<Sq<Y>> + TrainingDims<Y> + TrainingDims<Dimensionless>,
+    Sq<Y>: Dimensional,
+{
+    // loss name as eighth argument, the MSE by default
+    let loss_name = std::env::args().nth(8).unwrap_or("mse".to_string());
+    let loss = loss::by_name(&loss_name).unwrap_or_else(|| panic!("Unknown loss: {}", loss_name));
+    match loss.degree() {
+        2 => fit_with::<Y, Sq<Y>>(data, target),
+        1 => fit_with::<Y, Y>(data, target),
+        _ => fit_with::<Y, Dimensionless>(data, target),
+    }
+}
 
-    for e in 0..epochs {
-        let mut grads = Vector::<f64,Dimensionless,N>::zero();
-        
-        // Compute gradient for each coefficient
-        for k in 0..N {
-            let g = grad(&coeffs, k);
-            // Scale down the gradient for higher powers
-            let scale = 1.0 / (((k + 1) as f64).powf(1.5) as f64); // or try 1.0 / ((k + 1).pow(2) as f64)
-            grads.set_at(0, k, 0, g * dless!(scale));
-        }
-        
-        let g_norm = grads.norm();
-
-        // Gradient clipping
-        if g_norm > max_gnorm {
-            println!("Gradient norm exceeded threshold, normalizing.");
-            grads = grads * (max_gnorm / g_norm);
-        }   
-        
-        coeffs = coeffs - (grads * glr);
-
-        // Compute the loss using the MSE function
-        let ln = mse(&coeffs);
-        losses.push(l.raw());
-        let dl = ln - l;
-        l = ln;
-
-        
-
-        // shedule the learning rate decay
-        if dl > Scalar::zero() {
-            glr = glr * dless!(0.99);
-        } else if dl < Scalar::zero()  && e > 50{
-            glr = glr * (dless!(1.0)+(dless!(2.0*unsafe{threshold})+dl.mag())*dless!(20.0));
-            if glr > dless!(1e-3) {
-                glr = dless!(1e-6);
-            } else if glr < dless!(1e-6) {
-                glr = dless!(1e-6);
+fn fit_with<Y: Dimensional + TrainingDims<L>, L: Dimensional>(
+    data: Dataset,
+    target: Option<Target>,
+) -> Result<(), DimError> {
+    // optimizer name as first argument, plain SGD by default; gn, lm, lbfgs,
+    // nm, cmaes and pso run a solver in place of the epoch loop
+    let opt_name = std::env::args().nth(1).unwrap_or("sgd".to_string());
+    let solver = newton::Solver::by_name(&opt_name);
+
+    // scheduler name as second argument, the adaptive rule by default
+    let sched_name = std::env::args().nth(2).unwrap_or("adaptive".to_string());
+
+    // curriculum name as third argument, the loss plateau rule by default
+    let cur_name = std::env::args().nth(3).unwrap_or("plateau".to_string());
+
+    let (x_min, x_max) = data.range();
+
+    // model name as fourth argument, the monomial polynomial by default
+    let model_name = std::env::args().nth(4).unwrap_or("polynomial".to_string());
+
+    // hold out 15% for validation and 15% for the final test
+    let (train, val, test) = data.split(0.15, 0.15, Split::Random { seed: 42 });
+
+    // a fresh trainer from the arguments, once per start
+    let make_trainer = || {
+        let optimizer: Box<dyn Optimizer> = match solver {
+            Some(_) => Box::new(optim::Sgd),
+            None => optim::by_name(&opt_name).unwrap_or_else(|| panic!("Unknown optimizer: {}", opt_name)),
+        };
+        let scheduler: Box<dyn LrScheduler> = schedule::by_name(&sched_name, 1e-4)
+            .unwrap_or_else(|| panic!("Unknown scheduler: {}", sched_name));
+        let curriculum = Curriculum::by_name(&cur_name)
+            .unwrap_or_else(|| panic!("Unknown curriculum: {}", cur_name));
+        let model: Box<dyn Model> = model::by_name(&model_name, (x_min, x_max))
+            .unwrap_or_else(|| panic!("Unknown model: {}", model_name));
+
+        let mut trainer: Trainer<Y, L> = Trainer::new(model, train.clone(), optimizer, scheduler, curriculum);
+        trainer.val = Some(val.clone());
+        trainer.early_stopping = Some(EarlyStopping { patience: 1000, min_delta: Scalar::zero() });
+        // regularizer names as a comma separated seventh argument, none by default
+        if let Some(names) = std::env::args().nth(7) {
+            for name in names.split(',').filter(|n| !n.is_empty()) {
+                trainer.regularizers.push(regularize::by_name(name)
+                    .unwrap_or_else(|| panic!("Unknown regularizer: {}", name)));
             }
         }
-
-        if e % 5 == 0 {
-            println!("Gradient: {}", grads);
-            println!("Epoch: {}, Loss: {:+e}", e, l.raw());
-            println!("Glr: {}, Dl {}", glr.raw(),dl.mag().raw());
-
-            if dl > unsafe {dless!(threshold)} && e - last_conv > 500 {
-                println!("Converged at epoch {} with loss {}", e, l.raw());
-                last_conv = e as i32;
-                unsafe {
-                    if enabled < N {
-                        enabled += 1;
-                    }
-                    // scale the threshold inversely with the loss
-                    if dl.raw() < unsafe{threshold} && enabled > 1 {
-                        threshold = dl.raw() * 2.0;
-                    } 
-                }
-                
+        // loss name as eighth argument, the MSE by default
+        if let Some(name) = std::env::args().nth(8) {
+            trainer.loss = loss::by_name(&name).unwrap_or_else(|| panic!("Unknown loss: {}", name));
+        }
+        // line search name as ninth argument, a fixed learning rate by default
+        if let Some(name) = std::env::args().nth(9) {
+            trainer.line_search = Some(linesearch::LineSearch::by_name(&name)
+                .unwrap_or_else(|| panic!("Unknown line search: {}", name)));
+        }
+        // batch size as tenth argument, `size` or `size:seed`, 0 for full
+        // batch; mini-batches of 256 once there are more than 2000 samples
+        // by default, as they only pay off on larger datasets
+        let (size, seed) = match std::env::args().nth(10) {
+            Some(spec) => {
+                let (size, seed) = spec.split_once(':').unwrap_or((&spec, "42"));
+                (size.parse().unwrap_or_else(|_| panic!("Invalid batch size: {}", size)),
+                 seed.parse().unwrap_or_else(|_| panic!("Invalid batch seed: {}", seed)))
             }
+            None if trainer.data.len() > 2000 => (256, 42),
+            None => (0, 42),
+        };
+        if size > 0 {
+            trainer.batcher = Some(Batcher::new(size, seed));
         }
-
-        // Save visualizations every 100 epochs
-        if e % 100 == 0 && e > 0 {
-            let epoch_losses = &losses[0..=e as usize];
-            let loss_file = format!("loss_curve.html");
-            loss_curve(epoch_losses, &loss_file, Some(unsafe { threshold }))
-                .expect("Failed to create loss curve visualization");
-            
-            let f = |x: f64| infer(coeffs.clone(), x).raw();
-            let viz_file = format!("visualization.html");
-            plot_comparison(f, target, 0.0, 5.0, 500, &viz_file)
-                .expect("Failed to create function comparison visualization");
-            
-            println!("Saved visualizations for epoch {}", e);
+        // gradient mode (analytic, autodiff, dual, fd) as eleventh argument,
+        // the closed form by default
+        if let Some(name) = std::env::args().nth(11) {
+            trainer.grad_mode = GradMode::by_name(&name).unwrap_or_else(|| panic!("Unknown gradient mode: {}", name));
         }
+        trainer
+    };
+    let mut trainer = make_trainer();
+    trainer.check_dims()?;
+    println!("{}", trainer.coeffs);
+
+    println!("Samples: {}, Range: [{}, {}], Epochs: {}", trainer.data.len(), x_min, x_max, trainer.epochs);
+    println!("Dimensions: x in {}, y in {}", trainer.data.x_dim, trainer.data.y_dim);
+    println!("Model: {}, Loss: {}, Optimizer: {}, Scheduler: {}", trainer.model.name(), trainer.loss.name(), trainer.optimizer.name(), trainer.scheduler.name());
+    if let Some(target) = target {
+        println!("Target: {}", target(1.0));
+    }
 
+    // init strategies (zero, random, lstsq) as a comma separated fifth
+    // argument, zero by default; several of them train in parallel and the
+    // best start is kept
+    let init_names = std::env::args().nth(5).unwrap_or("zero".to_string());
+    let inits: Vec<Init> = init_names
+        .split(',')
+        .enumerate()
+        .map(|(i, name)| Init::by_name(name, 42 + i as u64).unwrap_or_else(|| panic!("Unknown init: {}", name)))
+        .collect();
+    inits[0].apply(&mut trainer);
+
+    // closed-form baseline
+    let baseline = trainer.lstsq(lstsq::Method::Svd);
+    if let Some(fit) = &baseline {
+        println!("Least squares: MSE {:+e}, max error {:+e}, R² {}, rank {}, cond {:e}",
+            fit.mse, fit.max_abs_err, fit.r2, fit.rank, fit.cond.unwrap_or(f64::NAN));
     }
 
+    let starting_loss = trainer.data_loss(&trainer.coeffs);
+    println!("Starting loss: {}", trainer.format_loss(starting_loss));
+
+    println!("Coeffs: {}", trainer.coeffs);
+
+    // epoch loop or solver, for one start as for several
+    let run = |t: &mut Trainer<Y, L>| match solver {
+        Some(s) => {
+            t.solve(s, &newton::SolveOptions::default());
+            t.data_loss(&t.coeffs)
+        }
+        None => t.train(),
+    };
+    let l = if inits.len() > 1 {
+        let (best, summary) = multistart::multi_start(make_trainer, run, &inits);
+        summary.print();
+        trainer = best;
+        summary.starts[summary.best].loss
+    } else {
+        run(&mut trainer)
+    };
+
     // Show a sampled version of the losses
-    loss_curve(&losses.iter().step_by(1).cloned().collect::<Vec<_>>(), "loss_curve.html", Some(unsafe { threshold }))
+    loss_curve(&trainer.losses.iter().step_by(1).cloned().collect::<Vec<_>>(), &[("Validation Loss", &trainer.val_losses), ("Regularization", &trainer.reg_losses)], "loss_curve.html", trainer.curriculum.threshold().map(|t| t.raw()))
         .expect("Failed to create loss curve visualization");
 
-    println!("Final Coeffs: {}", coeffs);
-    println!("Final Loss: {}", l);
-    println!("Starting Loss: {}", starting_loss);
+    let coeffs = trainer.coeffs;
+    println!("Final Coeffs: {}", dims::format_coeffs(&coeffs, &trainer.coeff_dims()));
+    if let Some(m) = trainer.model.monomial(&coeffs) {
+        let m_dims = dims::monomial_dims(trainer.data.x_dim, trainer.data.y_dim);
+        println!("Monomial Coeffs: {}", dims::format_coeffs(&m, &m_dims[..trainer.model.param_count()]));
+    }
+    println!("Final Loss: {}", trainer.format_loss(l));
+    println!("Final Penalty: {}", trainer.format_loss(trainer.penalty(&coeffs)));
+    println!("Optimizer steps: {}", trainer.steps);
+    println!("Evaluations: {} objective, {} gradient", trainer.evaluations, trainer.grad_evaluations);
+    if let Some((epoch, v, _)) = &trainer.best {
+        println!("Best Validation Loss: {} at epoch {}", trainer.format_loss(*v), epoch);
+    }
+    println!("Test Loss: {}", trainer.format_loss(trainer.loss_on(&coeffs, &test)));
+    println!("Starting Loss: {}", trainer.format_loss(starting_loss));
+    println!("Curriculum unlocks: {}", trainer.curriculum.events.len());
 
-    let f = |x: f64| infer(coeffs.clone(), x).raw();
+    let f = |x: f64| trainer.model.forward(&coeffs, x).raw();
 
     println!("f(1.0) = {}", f(1.0));
     println!("f(1.5) = {}", f(1.5));
     println!("f(2.0) = {}", f(2.0));
     println!("f(3.0) = {}", f(3.0));
 
-    println!("Target(1.0) = {}", target(1.0));
-    println!("Target(1.5) = {}", target(1.5));
-    println!("Target(2.0) = {}", target(2.0));
-    println!("Target(3.0) = {}", target(3.0));
+    if let Some(target) = target {
+        println!("Target(1.0) = {}", target(1.0));
+        println!("Target(1.5) = {}", target(1.5));
+        println!("Target(2.0) = {}", target(2.0));
+        println!("Target(3.0) = {}", target(3.0));
+    }
 
     // Visualization code
     let output_file = "visualization.html";
-    plot_comparison(f, target, 0.0, 5.0, 500, output_file)
+    plot_data(f, &trainer.data, output_file)
         .expect("Failed to create visualization");
+
+    // Predictions and residuals next to the plots
+    csv::write_predictions("predictions.csv", &trainer.data, f)
+        .expect("Failed to write predictions");
+    println!("Predictions saved to: predictions.csv");
+    npy::write_predictions("predictions.npy", &trainer.data, f)
+        .expect("Failed to write predictions");
+    println!("Predictions saved to: predictions.npy");
+
+    // Minimax polynomial of the same size, for comparison; it needs the
+    // target as a function, so loaded data has none
+    if let Some(target) = target {
+        let mm = remez::remez(target, N - 1, 0.0, 5.0, 50, 1e-6);
+        println!("Remez: max error {:+e}, levelled error {:+e}, {} iterations, converged: {}",
+            mm.max_error, mm.levelled_error, mm.iterations, mm.converged);
+        println!("Remez Coeffs: {}", mm.coeffs);
+        let poly = Polynomial::new(N);
+        plot_comparison(|x: f64| poly.forward(&mm.coeffs, x).raw(), target, 0.0, 5.0, 500, "remez.html")
+            .expect("Failed to create Remez visualization");
+    }
+    Ok(())
 }
@@ -0,0 +1,365 @@
+use std::f64::consts::PI;
+
+use slut::{complex::c64, dimension::Dimensionless, dot, tensor::*};
+
+use crate::autodiff::{Var, VarVector};
+use crate::complex::{self, ComplexParams};
+use crate::dims::{self, Dim};
+use crate::dual::{self, Dual, DualParams};
+use crate::{N, Params};
+
+/// A parametric function `y = f(params, x)` the trainer can fit.
+///
+/// Parameters live in a fixed `Params`; models using
+/// fewer than `N` leave the rest at zero. Models that are linear in their
+/// parameters only need `features` to get gradients, autodiff and dual
+/// support for free.
+pub trait Model: Send {
+    fn name(&self) -> &'static str;
+
+    /// How many leading entries of the parameter vector are used.
+    fn param_count(&self) -> usize;
+
+    fn forward(&self, params: &Params, x: f64) -> Scalar<f64, Dimensionless>;
+
+    /// Starting parameters.
+    fn init_params(&self) -> Params {
+        Params::zero()
+    }
+
+    /// Basis values at `x` when `forward` is `dot!(params, features)`.
+    fn features(&self, _x: f64) -> Option<Params> {
+        None
+    }
+
+    /// `∂forward/∂params` at `x`, if known in closed form.
+    fn param_grad(&self, _params: &Params, x: f64) -> Option<Params> {
+        self.features(x)
+    }
+
+    /// Forward pass on tracked parameters, for reverse-mode autodiff.
+    fn forward_var<'t>(&self, params: &VarVector<'t>, x: f64) -> Option<Var<'t>> {
+        self.features(x).map(|f| params.dot(&f))
+    }
+
+    /// Forward pass on dual parameters, for forward-mode differentiation.
+    fn forward_dual(&self, params: &DualParams, x: f64) -> Option<Dual<Dimensionless>> {
+        self.features(x).map(|f| dual::dot(params, &f))
+    }
+
+    /// Forward pass on complex parameters, for complex-step differentiation.
+    fn forward_complex(&self, params: &ComplexParams, x: f64) -> Option<c64> {
+        self.features(x).map(|f| complex::dot(params, &f))
+    }
+
+    /// The fitted function as plain monomial coefficients `Σ m_k x^k`, when
+    /// it is a polynomial.
+    fn monomial(&self, _params: &Params) -> Option<Params> {
+        None
+    }
+
+    /// Per-parameter gradient scaling applied by the trainer.
+    fn grad_scale(&self, _k: usize) -> f64 {
+        1.0
+    }
+
+    /// Dimension of each used parameter when `x` has dimension `x` and the
+    /// output `y`. Models over dimensionless features have every parameter
+    /// in `[y]`.
+    fn coeff_dims(&self, _x: Dim, y: Dim) -> Vec<Dim> {
+        vec![y; self.param_count()]
+    }
+}
+
+/// Polynomial family used as the basis of a `Polynomial` model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Basis {
+    /// `1, x, x², …` on raw `x`
+    Monomial,
+    /// Chebyshev polynomials of the first kind `T_k`
+    Chebyshev,
+    /// Chebyshev polynomials of the second kind `U_k`
+    Chebyshev2,
+    /// Legendre polynomials `P_k`
+    Legendre,
+    /// Physicists' Hermite polynomials `H_k`
+    Hermite,
+}
+
+impl Basis {
+    // (a, b, c) such that p_{n+1} = (a t + b) p_n - c p_{n-1}, with p_0 = 1
+    fn recurrence(&self, n: usize) -> (f64, f64, f64) {
+        let n = n as f64;
+        match self {
+            Basis::Monomial => (1.0, 0.0, 0.0),
+            Basis::Chebyshev => (if n == 0.0 { 1.0 } else { 2.0 }, 0.0, 1.0),
+            Basis::Chebyshev2 => (2.0, 0.0, 1.0),
+            Basis::Legendre => ((2.0 * n + 1.0) / (n + 1.0), 0.0, n / (n + 1.0)),
+            Basis::Hermite => (2.0, 0.0, 2.0 * n),
+        }
+    }
+
+    /// `[p_0(t), …, p_{terms-1}(t)]`
+    pub fn eval(&self, t: f64, terms: usize) -> [f64; N] {
+        let mut p = [0.0f64; N];
+        if terms == 0 {
+            return p;
+        }
+        p[0] = 1.0;
+        for n in 0..terms.min(N) - 1 {
+            let (a, b, c) = self.recurrence(n);
+            let prev = if n == 0 { 0.0 } else { p[n - 1] };
+            p[n + 1] = (a * t + b) * p[n] - c * prev;
+        }
+        p
+    }
+
+    /// Monomial coefficients of each `p_k(t)`, row `k` holding `p_k`.
+    pub fn monomials(&self, terms: usize) -> [[f64; N]; N] {
+        let mut m = [[0.0f64; N]; N];
+        if terms == 0 {
+            return m;
+        }
+        m[0][0] = 1.0;
+        for n in 0..terms.min(N) - 1 {
+            let (a, b, c) = self.recurrence(n);
+            for j in 0..N {
+                let shifted = if j == 0 { 0.0 } else { m[n][j - 1] };
+                let prev = if n == 0 { 0.0 } else { m[n - 1][j] };
+                m[n + 1][j] = a * shifted + b * m[n][j] - c * prev;
+            }
+        }
+        m
+    }
+}
+
+/// `y = Σ c_k p_k(t)` over the first `terms` basis polynomials.
+///
+/// For the orthogonal bases `x` is first mapped linearly from `domain` onto
+/// `[-1, 1]`, where they are well conditioned. The monomial basis uses `x`
+/// as is.
+pub struct Polynomial {
+    pub terms: usize,
+    pub basis: Basis,
+    pub domain: (f64, f64),
+}
+
+impl Polynomial {
+    pub fn new(terms: usize) -> Self {
+        Polynomial::with_basis(terms, Basis::Monomial, (-1.0, 1.0))
+    }
+
+    pub fn with_basis(terms: usize, basis: Basis, domain: (f64, f64)) -> Self {
+        Polynomial { terms: terms.min(N), basis, domain }
+    }
+
+    // t = scale·x + shift
+    fn mapping(&self) -> (f64, f64) {
+        if self.basis == Basis::Monomial {
+            return (1.0, 0.0);
+        }
+        let (a, b) = self.domain;
+        (2.0 / (b - a), -(a + b) / (b - a))
+    }
+
+    /// Equivalent coefficients in the plain monomial form `Σ m_k x^k`, for
+    /// export and display.
+    pub fn to_monomial(&self, params: &Params) -> Params {
+        let c = crate::to_array(params);
+        let rows = self.basis.monomials(self.terms);
+
+        // collapse to a single polynomial in t
+        let mut in_t = [0.0f64; N];
+        for k in 0..self.terms {
+            for (t, r) in in_t.iter_mut().zip(&rows[k]) {
+                *t += c[k] * r;
+            }
+        }
+
+        // substitute t = scale·x + shift with Horner's scheme
+        let (scale, shift) = self.mapping();
+        let mut in_x = [0.0f64; N];
+        for k in (0..N).rev() {
+            let mut next = [0.0f64; N];
+            for j in 0..N {
+                next[j] += shift * in_x[j];
+                if j + 1 < N {
+                    next[j + 1] += scale * in_x[j];
+                }
+            }
+            next[0] += in_t[k];
+            in_x = next;
+        }
+        crate::from_array(in_x)
+    }
+}
+
+impl Model for Polynomial {
+    fn name(&self) -> &'static str {
+        match self.basis {
+            Basis::Monomial => "polynomial",
+            Basis::Chebyshev => "chebyshev",
+            Basis::Chebyshev2 => "chebyshev2",
+            Basis::Legendre => "legendre",
+            Basis::Hermite => "hermite",
+        }
+    }
+
+    fn param_count(&self) -> usize {
+        self.terms
+    }
+
+    fn forward(&self, params: &Params, x: f64) -> Scalar<f64, Dimensionless> {
+        let inputs = self.features(x).unwrap();
+        dot!(*params, inputs)
+    }
+
+    fn features(&self, x: f64) -> Option<Params> {
+        // create a vector like [p₀(t), p₁(t), ... pₙ₋₁(t)]
+        let (scale, shift) = self.mapping();
+        let input_data = self.basis.eval(scale * x + shift, self.terms);
+        Some(Params::default(input_data))
+    }
+
+    fn monomial(&self, params: &Params) -> Option<Params> {
+        Some(self.to_monomial(params))
+    }
+
+    // Scale down the gradient for higher powers, the orthogonal bases are
+    // conditioned well enough without it
+    fn grad_scale(&self, k: usize) -> f64 {
+        match self.basis {
+            Basis::Monomial => 1.0 / ((k + 1) as f64).powf(1.5), // or try 1.0 / ((k + 1).pow(2) as f64)
+            _ => 1.0,
+        }
+    }
+
+    // The orthogonal bases work on the dimensionless t, so only the
+    // monomial basis has coefficients in [y]/[x]^k
+    fn coeff_dims(&self, x: Dim, y: Dim) -> Vec<Dim> {
+        match self.basis {
+            Basis::Monomial => dims::monomial_dims(x, y)[..self.terms].to_vec(),
+            _ => vec![y; self.terms],
+        }
+    }
+}
+
+/// Truncated Fourier series `a₀ + Σ aₖ cos(kωx) + bₖ sin(kωx)` with
+/// `ω = 2π / period`, parameters stored as `[a₀, a₁, b₁, a₂, b₂, …]`.
+pub struct Fourier {
+    pub terms: usize,
+    pub period: f64,
+}
+
+impl Fourier {
+    pub fn new(terms: usize, period: f64) -> Self {
+        Fourier { terms: terms.min(N), period }
+    }
+}
+
+impl Model for Fourier {
+    fn name(&self) -> &'static str {
+        "fourier"
+    }
+
+    fn param_count(&self) -> usize {
+        self.terms
+    }
+
+    fn forward(&self, params: &Params, x: f64) -> Scalar<f64, Dimensionless> {
+        let inputs = self.features(x).unwrap();
+        dot!(*params, inputs)
+    }
+
+    fn features(&self, x: f64) -> Option<Params> {
+        let w = 2.0 * PI / self.period;
+        let mut input_data = [0.0f64; N];
+        for (i, v) in input_data.iter_mut().enumerate().take(self.terms) {
+            let k = i.div_ceil(2);
+            *v = if i == 0 {
+                1.0
+            } else if i % 2 == 1 {
+                (k as f64 * w * x).cos()
+            } else {
+                (k as f64 * w * x).sin()
+            };
+        }
+        Some(Params::default(input_data))
+    }
+}
+
+/// Look up a model with its default settings, orthogonal bases mapped from
+/// `domain`.
+pub fn by_name(name: &str, domain: (f64, f64)) -> Option<Box<dyn Model>> {
+    let m: Box<dyn Model> = match name {
+        "polynomial" => Box::new(Polynomial::new(N)),
+        "chebyshev" => Box::new(Polynomial::with_basis(N, Basis::Chebyshev, domain)),
+        "chebyshev2" => Box::new(Polynomial::with_basis(N, Basis::Chebyshev2, domain)),
+        "legendre" => Box::new(Polynomial::with_basis(N, Basis::Legendre, domain)),
+        "hermite" => Box::new(Polynomial::with_basis(N, Basis::Hermite, domain)),
+        "fourier" => Box::new(Fourier::new(N, 2.0 * (domain.1 - domain.0))),
+        _ => return None,
+    };
+    Some(m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_array, to_array};
+
+    const BASES: [Basis; 5] = [Basis::Monomial, Basis::Chebyshev, Basis::Chebyshev2, Basis::Legendre, Basis::Hermite];
+
+    #[test]
+    fn bases_match_their_closed_forms() {
+        let t: f64 = 0.5;
+        let p = |b: Basis| b.eval(t, 4);
+        assert_eq!(p(Basis::Monomial)[..4], [1.0, 0.5, 0.25, 0.125]);
+        // T₃ = 4t³ - 3t, U₃ = 8t³ - 4t, P₃ = (5t³ - 3t)/2, H₃ = 8t³ - 12t
+        assert_eq!(p(Basis::Chebyshev)[3], 4.0 * t.powi(3) - 3.0 * t);
+        assert_eq!(p(Basis::Chebyshev2)[3], 8.0 * t.powi(3) - 4.0 * t);
+        assert!((p(Basis::Legendre)[3] - (5.0 * t.powi(3) - 3.0 * t) / 2.0).abs() < 1e-15);
+        assert_eq!(p(Basis::Hermite)[3], 8.0 * t.powi(3) - 12.0 * t);
+        // only the requested terms are filled
+        assert!(p(Basis::Hermite)[4..].iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn to_monomial_keeps_the_function() {
+        let c = from_array(std::array::from_fn(|k| 1.0 / (k as f64 + 1.0) - 0.3));
+        for basis in BASES {
+            let model = Polynomial::with_basis(6, basis, (2.0, 5.0));
+            let m = to_array(&model.to_monomial(&c));
+            for x in [2.0, 2.7, 4.1, 5.0] {
+                let direct = model.forward(&c, x).raw();
+                let horner = m.iter().rev().fold(0.0, |acc, mk| acc * x + mk);
+                assert!((direct - horner).abs() < 1e-9 * direct.abs().max(1.0), "{:?} at {}", basis, x);
+            }
+            assert!(m[6..].iter().all(|&v| v == 0.0));
+        }
+    }
+
+    #[test]
+    fn domain_maps_onto_the_unit_interval() {
+        let model = Polynomial::with_basis(2, Basis::Chebyshev, (2.0, 5.0));
+        let t = |x: f64| to_array(&model.features(x).unwrap())[1];
+        assert!((t(2.0) + 1.0).abs() < 1e-15 && t(3.5).abs() < 1e-15 && (t(5.0) - 1.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn fourier_features() {
+        let f = to_array(&Fourier::new(5, 4.0).features(1.0).unwrap());
+        // ωx = π/2: [1, cos, sin, cos 2·, sin 2·]
+        let want = [1.0, 0.0, 1.0, -1.0, 0.0];
+        assert!(f[..5].iter().zip(want).all(|(v, w)| (v - w).abs() < 1e-15), "{:?}", f);
+        assert!(f[5..].iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn lookup_by_name() {
+        for name in ["polynomial", "chebyshev", "chebyshev2", "legendre", "hermite", "fourier"] {
+            assert_eq!(by_name(name, (0.0, 1.0)).unwrap().name(), name);
+        }
+        assert!(by_name("spline", (0.0, 1.0)).is_none());
+    }
+}
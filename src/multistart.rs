@@ -0,0 +1,216 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use slut::tensor::*;
+
+use crate::dims::{Dimensional, TrainingDims};
+use crate::lstsq::Method;
+use crate::train::Trainer;
+use crate::{from_array, to_array, Params};
+
+/// Where a start begins.
+#[derive(Clone, Copy, Debug)]
+pub enum Init {
+    /// The model's own initial parameters, zero for the built-in models.
+    Zero,
+    /// The initial parameters plus uniform noise in `[-scale, scale]` on the
+    /// coefficients the curriculum starts with, zero on the locked ones.
+    Random { scale: f64, seed: u64 },
+    /// The least-squares solution, with the curriculum turned off as in
+    /// `Trainer::seed_lstsq`. Falls back to `Zero` when the fit fails.
+    Lstsq,
+}
+
+impl Init {
+    /// Look up a strategy, `seed` only used by `random`.
+    pub fn by_name(name: &str, seed: u64) -> Option<Self> {
+        match name {
+            "zero" => Some(Init::Zero),
+            "random" => Some(Init::Random { scale: 0.1, seed }),
+            "lstsq" => Some(Init::Lstsq),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Init::Zero => "zero",
+            Init::Random { .. } => "random",
+            Init::Lstsq => "lstsq",
+        }
+    }
+
+    /// Set the starting parameters of a fresh trainer.
+    pub fn apply<Y: Dimensional + TrainingDims<L>, L: Dimensional>(&self, trainer: &mut Trainer<Y, L>) {
+        match *self {
+            Init::Zero => {}
+            Init::Random { scale, seed } => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let enabled = trainer.curriculum.enabled().min(trainer.model.param_count());
+                let mut c = to_array(&trainer.coeffs);
+                for (k, v) in c.iter_mut().enumerate() {
+                    *v = if k < enabled { *v + rng.random_range(-scale..=scale) } else { 0.0 };
+                }
+                trainer.coeffs = from_array(c);
+            }
+            Init::Lstsq => {
+                trainer.seed_lstsq(Method::Svd);
+            }
+        }
+    }
+}
+
+/// Outcome of one start.
+#[derive(Clone, Debug)]
+pub struct Start<L: Dimensional> {
+    pub init: Init,
+    pub coeffs: Params,
+    /// Final training loss.
+    pub loss: Scalar<f64, L>,
+    /// Final validation loss, if there is a validation set.
+    pub val_loss: Option<Scalar<f64, L>>,
+    pub epochs: usize,
+    pub steps: usize,
+    pub unlocks: usize,
+}
+
+impl<L: Dimensional> Start<L> {
+    /// What the starts are ranked by: the validation loss when there is
+    /// one, the training loss otherwise.
+    pub fn score(&self) -> Scalar<f64, L> {
+        self.val_loss.unwrap_or(self.loss)
+    }
+}
+
+/// Every start, in the order of the strategies, and which one won.
+#[derive(Clone, Debug)]
+pub struct Summary<L: Dimensional> {
+    pub starts: Vec<Start<L>>,
+    pub best: usize,
+}
+
+impl<L: Dimensional> Summary<L> {
+    pub fn print(&self) {
+        for (i, s) in self.starts.iter().enumerate() {
+            let val = s.val_loss.map_or("-".to_string(), |v| format!("{:+e}", v.raw()));
+            println!("Start {} ({}): loss {:+e}, val {}, {} epochs, {} unlocks{}",
+                i, s.init.name(), s.loss.raw(), val, s.epochs, s.unlocks, if i == self.best { ", best" } else { "" });
+        }
+    }
+}
+
+/// Train one fresh trainer per strategy and keep the one with the lowest
+/// `Start::score`.
+///
+/// `make` builds the trainers, so every start gets its own optimizer,
+/// scheduler and curriculum state, and `run` trains one and returns its
+/// final loss, e.g. `Trainer::train` or a `Trainer::solve`. The starts run
+/// quietly and without intermediate plots, on at most
+/// `available_parallelism` threads.
+pub fn multi_start<Y, L, F, R>(make: F, run: R, inits: &[Init]) -> (Trainer<Y, L>, Summary<L>)
+where
+    Y: Dimensional + TrainingDims<L>,
+    L: Dimensional,
+    F: Fn() -> Trainer<Y, L> + Sync,
+    R: Fn(&mut Trainer<Y, L>) -> Scalar<f64, L> + Sync,
+{
+    assert!(!inits.is_empty(), "multi-start needs at least one strategy");
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(inits.len());
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(inits.len()));
+    thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| {
+                // each worker takes the next start until none are left
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(init) = inits.get(i) else { break };
+                    let mut t = make();
+                    t.verbose = false;
+                    t.plot_every = 0;
+                    init.apply(&mut t);
+                    let l = run(&mut t);
+                    results.lock().unwrap().push((i, t, l));
+                }
+            });
+        }
+    });
+    // back in the order of the strategies
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _, _)| *i);
+    let mut trainers: Vec<(Trainer<Y, L>, Scalar<f64, L>)> = results.into_iter().map(|(_, t, l)| (t, l)).collect();
+
+    let starts: Vec<Start<L>> = trainers
+        .iter()
+        .zip(inits)
+        .map(|((t, l), init)| Start {
+            init: *init,
+            coeffs: t.coeffs,
+            loss: *l,
+            val_loss: t.val_loss(&t.coeffs),
+            epochs: t.losses.len(),
+            steps: t.steps,
+            unlocks: t.curriculum.events.len(),
+        })
+        .collect();
+    let best = (0..starts.len())
+        .min_by(|&a, &b| starts[a].score().raw().total_cmp(&starts[b].score().raw()))
+        .unwrap();
+
+    let (trainer, _) = trainers.swap_remove(best);
+    (trainer, Summary { starts, best })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curriculum::Curriculum;
+    use crate::data::Dataset;
+    use crate::model::Polynomial;
+    use crate::optim::Adam;
+    use crate::schedule::Constant;
+
+    fn trainer(curriculum: Curriculum) -> Trainer {
+        let data = Dataset::grid(|x| 1.0 + 2.0 * x, 0.0, 0.05, 20);
+        let mut t = Trainer::new(Box::new(Polynomial::new(3)), data, Box::new(Adam::new(0.9, 0.999)), Box::new(Constant), curriculum);
+        t.lr = Scalar::default([1e-2]);
+        t.epochs = 200;
+        t.verbose = false;
+        t.plot_every = 0;
+        t
+    }
+
+    #[test]
+    fn random_only_perturbs_unlocked_coefficients() {
+        let mut t = trainer(Curriculum::epochs(100));
+        Init::Random { scale: 0.5, seed: 7 }.apply(&mut t);
+        let c = to_array(&t.coeffs);
+        assert!(c[..3].iter().any(|&v| v != 0.0));
+        assert!(c[3..].iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn random_is_reproducible() {
+        let (mut a, mut b) = (trainer(Curriculum::off()), trainer(Curriculum::off()));
+        Init::Random { scale: 0.5, seed: 3 }.apply(&mut a);
+        Init::Random { scale: 0.5, seed: 3 }.apply(&mut b);
+        assert_eq!(to_array(&a.coeffs), to_array(&b.coeffs));
+    }
+
+    #[test]
+    fn keeps_the_best_start_in_order() {
+        let inits: Vec<Init> = (0..5).map(|i| Init::Random { scale: 1.0, seed: i }).chain([Init::Lstsq]).collect();
+        let (best, summary) = multi_start(|| trainer(Curriculum::off()), |t| t.train(), &inits);
+        assert_eq!(summary.starts.len(), inits.len());
+        for (s, init) in summary.starts.iter().zip(&inits) {
+            assert_eq!(s.init.name(), init.name());
+        }
+        let scores: Vec<f64> = summary.starts.iter().map(|s| s.score().raw()).collect();
+        assert!(scores.iter().all(|&s| s >= scores[summary.best]));
+        assert_eq!(to_array(&best.coeffs), to_array(&summary.starts[summary.best].coeffs));
+    }
+}
@@ -0,0 +1,443 @@
+use crate::data::Dataset;
+use crate::grad::residuals;
+use crate::linesearch::{dot, LineSearch};
+use crate::lstsq::solve_qr;
+use crate::model::Model;
+use crate::{from_array, to_array, N, Params};
+
+/// Second-order, quasi-Newton and derivative-free methods, run instead of
+/// the epoch loop.
+#[derive(Clone, Copy, Debug)]
+pub enum Solver {
+    /// Gauss-Newton on the residuals, halving the step until the MSE drops.
+    GaussNewton,
+    /// Gauss-Newton with adaptive Marquardt damping `λ·diag(JᵀJ)`.
+    LevenbergMarquardt,
+    /// Limited-memory BFGS on the full objective, keeping `memory` pairs.
+    Lbfgs { memory: usize },
+    /// Nelder–Mead simplex on the full objective.
+    NelderMead,
+    /// CMA-ES starting from a Gaussian of width `sigma`.
+    CmaEs { sigma: f64, seed: u64 },
+    /// Particle swarm of `particles`, spread uniformly by `spread`.
+    Pso { particles: usize, spread: f64, seed: u64 },
+}
+
+impl Solver {
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "gn" => Some(Solver::GaussNewton),
+            "lm" => Some(Solver::LevenbergMarquardt),
+            "lbfgs" => Some(Solver::Lbfgs { memory: 8 }),
+            "nm" => Some(Solver::NelderMead),
+            "cmaes" => Some(Solver::CmaEs { sigma: 0.5, seed: 42 }),
+            "pso" => Some(Solver::Pso { particles: 30, spread: 1.0, seed: 42 }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solver::GaussNewton => "gauss-newton",
+            Solver::LevenbergMarquardt => "levenberg-marquardt",
+            Solver::Lbfgs { .. } => "lbfgs",
+            Solver::NelderMead => "nelder-mead",
+            Solver::CmaEs { .. } => "cma-es",
+            Solver::Pso { .. } => "pso",
+        }
+    }
+}
+
+/// Stopping rules shared by the solvers.
+#[derive(Clone, Copy, Debug)]
+pub struct SolveOptions {
+    pub max_iter: usize,
+    /// Stop once the gradient norm is below this.
+    pub gtol: f64,
+    /// Stop once the loss changes by less than this, relative.
+    pub ftol: f64,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        SolveOptions { max_iter: 200, gtol: 1e-10, ftol: 1e-12 }
+    }
+}
+
+/// Outcome of a solver run.
+#[derive(Clone, Debug)]
+pub struct SolveReport {
+    pub coeffs: Params,
+    pub loss: f64,
+    pub iterations: usize,
+    /// Loss evaluations, trial steps included.
+    pub evaluations: usize,
+    /// Gradient or Jacobian evaluations.
+    pub grad_evaluations: usize,
+    /// Final Levenberg–Marquardt damping `λ`.
+    pub damping: Option<f64>,
+    pub converged: bool,
+    pub reason: &'static str,
+    /// Loss after every iteration.
+    pub history: Vec<f64>,
+}
+
+fn mse(r: &[f64]) -> f64 {
+    r.iter().map(|r| r * r).sum::<f64>() / r.len() as f64
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|v| v * v).sum::<f64>().sqrt()
+}
+
+/// `∂r_i/∂c_k` of the residuals, from `param_grad` or by central
+/// differences when the model has none.
+pub fn jacobian<M>(model: &M, c: &Params, data: &Dataset, h: f64) -> Vec<[f64; N]>
+where
+    M: Model + ?Sized,
+{
+    data.x
+        .iter()
+        .map(|&x| match model.param_grad(c, x) {
+            Some(g) => to_array(&g),
+            None => std::array::from_fn(|k| {
+                if k >= model.param_count() {
+                    return 0.0;
+                }
+                let mut cp = to_array(c);
+                let mut cm = cp;
+                cp[k] += h;
+                cm[k] -= h;
+                (model.forward(&from_array(cp), x).raw() - model.forward(&from_array(cm), x).raw()) / (2.0 * h)
+            }),
+        })
+        .collect()
+}
+
+// gradient of the MSE, 2/M Jᵀr
+fn mse_gradient(j: &[[f64; N]], r: &[f64]) -> [f64; N] {
+    let m = r.len() as f64;
+    let mut g = [0.0f64; N];
+    for (row, r) in j.iter().zip(r) {
+        for (g, jk) in g.iter_mut().zip(row) {
+            *g += 2.0 * jk * r / m;
+        }
+    }
+    g
+}
+
+/// Minimize the MSE of `model` over `data` with Gauss-Newton steps, each the
+/// least-squares solution of `J δ = -r`.
+pub fn gauss_newton<M>(model: &M, data: &Dataset, c0: &Params, opts: &SolveOptions) -> SolveReport
+where
+    M: Model + ?Sized,
+{
+    let n = model.param_count();
+    let mut c = to_array(c0);
+    let mut r = residuals(model, c0, data);
+    let mut loss = mse(&r);
+    let mut report = SolveReport::start(c0, loss);
+
+    while report.iterations < opts.max_iter {
+        let j = jacobian(model, &from_array(c), data, 1e-6);
+        report.grad_evaluations += 1;
+        if norm(&mse_gradient(&j, &r)) < opts.gtol {
+            return report.finish(c, loss, true, "gradient below tolerance");
+        }
+
+        let neg_r: Vec<f64> = r.iter().map(|r| -r).collect();
+        let (delta, _) = solve_qr(j, neg_r, n);
+
+        // halve the step until the loss goes down
+        let mut t = 1.0;
+        let mut accepted = None;
+        for _ in 0..30 {
+            let trial: [f64; N] = std::array::from_fn(|k| c[k] + t * delta[k]);
+            let tr = residuals(model, &from_array(trial), data);
+            report.evaluations += 1;
+            if mse(&tr) < loss {
+                accepted = Some((trial, tr));
+                break;
+            }
+            t *= 0.5;
+        }
+        report.iterations += 1;
+
+        let Some((trial, tr)) = accepted else {
+            return report.finish(c, loss, false, "no decrease along the Gauss-Newton step");
+        };
+        let new_loss = mse(&tr);
+        let change = (loss - new_loss).abs();
+        c = trial;
+        r = tr;
+        loss = new_loss;
+        report.history.push(loss);
+        if change <= opts.ftol * loss.max(f64::MIN_POSITIVE) {
+            return report.finish(c, loss, true, "loss change below tolerance");
+        }
+    }
+    report.finish(c, loss, false, "iteration limit")
+}
+
+/// Minimize the MSE of `model` over `data` with Levenberg–Marquardt. The
+/// damped system `(JᵀJ + λ·diag(JᵀJ)) δ = -Jᵀr` is solved as an augmented
+/// least-squares problem; `λ` shrinks after a successful step and grows
+/// after a rejected one.
+pub fn levenberg_marquardt<M>(model: &M, data: &Dataset, c0: &Params, opts: &SolveOptions) -> SolveReport
+where
+    M: Model + ?Sized,
+{
+    let n = model.param_count();
+    let mut c = to_array(c0);
+    let mut r = residuals(model, c0, data);
+    let mut loss = mse(&r);
+    let mut lambda = 1e-3;
+    let mut report = SolveReport::start(c0, loss);
+
+    while report.iterations < opts.max_iter {
+        let j = jacobian(model, &from_array(c), data, 1e-6);
+        report.grad_evaluations += 1;
+        if norm(&mse_gradient(&j, &r)) < opts.gtol {
+            report.damping = Some(lambda);
+            return report.finish(c, loss, true, "gradient below tolerance");
+        }
+
+        let mut diag = [0.0f64; N];
+        for row in &j {
+            for (d, jk) in diag.iter_mut().zip(row).take(n) {
+                *d += jk * jk;
+            }
+        }
+
+        // retry with more damping until a step is accepted
+        loop {
+            let mut a = j.clone();
+            let mut b: Vec<f64> = r.iter().map(|r| -r).collect();
+            for (k, d) in diag.iter().enumerate().take(n) {
+                let mut row = [0.0f64; N];
+                row[k] = (lambda * d.max(1e-12)).sqrt();
+                a.push(row);
+                b.push(0.0);
+            }
+            let (delta, _) = solve_qr(a, b, n);
+            let trial: [f64; N] = std::array::from_fn(|k| c[k] + delta[k]);
+            let tr = residuals(model, &from_array(trial), data);
+            report.evaluations += 1;
+
+            let new_loss = mse(&tr);
+            if new_loss < loss {
+                let change = loss - new_loss;
+                c = trial;
+                r = tr;
+                loss = new_loss;
+                lambda = (lambda / 10.0).max(1e-12);
+                report.iterations += 1;
+                report.history.push(loss);
+                report.damping = Some(lambda);
+                if change <= opts.ftol * loss.max(f64::MIN_POSITIVE) {
+                    return report.finish(c, loss, true, "loss change below tolerance");
+                }
+                break;
+            }
+            lambda *= 10.0;
+            if lambda > 1e12 {
+                report.damping = Some(lambda);
+                return report.finish(c, loss, false, "damping limit");
+            }
+        }
+    }
+    report.damping = Some(lambda);
+    report.finish(c, loss, false, "iteration limit")
+}
+
+/// Minimize any smooth `f` with gradient `grad` by L-BFGS, keeping the last
+/// `memory` curvature pairs. Steps are chosen by `search`, strong Wolfe
+/// being the usual choice as it keeps the curvature pairs positive.
+pub fn lbfgs<F, G>(
+    f: F,
+    grad: G,
+    c0: &Params,
+    memory: usize,
+    search: &LineSearch,
+    opts: &SolveOptions,
+) -> SolveReport
+where
+    F: Fn(&Params) -> f64,
+    G: Fn(&Params) -> Params,
+{
+    let mut c = to_array(c0);
+    let mut loss = f(c0);
+    let mut g = to_array(&grad(c0));
+    let mut report = SolveReport::start(c0, loss);
+    report.grad_evaluations = 1;
+    let mut pairs: Vec<([f64; N], [f64; N], f64)> = Vec::new();
+
+    while report.iterations < opts.max_iter {
+        if norm(&g) < opts.gtol {
+            return report.finish(c, loss, true, "gradient below tolerance");
+        }
+
+        let mut d = two_loop(&g, &pairs);
+        if dot(&g, &d) >= 0.0 {
+            // not a descent direction, restart from steepest descent
+            pairs.clear();
+            d = g.map(|g| -g);
+        }
+
+        // without curvature information the first step is scaled to unit length
+        let t0 = if pairs.is_empty() { (1.0 / norm(&g)).min(1.0) } else { 1.0 };
+        let step = search.search(&f, &grad, &from_array(c), &from_array(d), loss, &from_array(g), t0);
+        report.evaluations += step.evaluations;
+        report.grad_evaluations += step.grad_evaluations;
+        report.iterations += 1;
+
+        if step.t == 0.0 {
+            return report.finish(c, loss, false, "line search failed");
+        }
+        let trial = to_array(&step.coeffs);
+        let new_loss = step.value;
+        let new_g = match &step.grad {
+            Some(g) => to_array(g),
+            None => {
+                report.grad_evaluations += 1;
+                to_array(&grad(&step.coeffs))
+            }
+        };
+        let s: [f64; N] = std::array::from_fn(|k| trial[k] - c[k]);
+        let y: [f64; N] = std::array::from_fn(|k| new_g[k] - g[k]);
+        let sy = dot(&s, &y);
+        // skip pairs that would break positive definiteness
+        if sy > 1e-12 * norm(&s) * norm(&y) {
+            pairs.push((s, y, 1.0 / sy));
+            if pairs.len() > memory {
+                pairs.remove(0);
+            }
+        }
+
+        let change = (loss - new_loss).abs();
+        c = trial;
+        g = new_g;
+        loss = new_loss;
+        report.history.push(loss);
+        if change <= opts.ftol * loss.abs().max(f64::MIN_POSITIVE) {
+            return report.finish(c, loss, true, "loss change below tolerance");
+        }
+    }
+    report.finish(c, loss, false, "iteration limit")
+}
+
+// L-BFGS two-loop recursion, -H·g for the implicit inverse Hessian H
+fn two_loop(g: &[f64; N], pairs: &[([f64; N], [f64; N], f64)]) -> [f64; N] {
+    let mut q = *g;
+    let mut alpha = vec![0.0; pairs.len()];
+    for (i, (s, y, rho)) in pairs.iter().enumerate().rev() {
+        alpha[i] = rho * dot(s, &q);
+        for (q, y) in q.iter_mut().zip(y) {
+            *q -= alpha[i] * y;
+        }
+    }
+    // initial scaling sᵀy / yᵀy from the latest pair
+    let gamma = pairs.last().map_or(1.0, |(s, y, _)| dot(s, y) / dot(y, y));
+    let mut z = q.map(|q| gamma * q);
+    for (i, (s, y, rho)) in pairs.iter().enumerate() {
+        let beta = rho * dot(y, &z);
+        for (z, s) in z.iter_mut().zip(s) {
+            *z += s * (alpha[i] - beta);
+        }
+    }
+    z.map(|z| -z)
+}
+
+impl SolveReport {
+    pub(crate) fn start(c0: &Params, loss: f64) -> Self {
+        SolveReport {
+            coeffs: *c0,
+            loss,
+            iterations: 0,
+            evaluations: 1,
+            grad_evaluations: 0,
+            damping: None,
+            converged: false,
+            reason: "",
+            history: Vec::new(),
+        }
+    }
+
+    pub(crate) fn finish(mut self, c: [f64; N], loss: f64, converged: bool, reason: &'static str) -> Self {
+        self.coeffs = from_array(c);
+        self.loss = loss;
+        self.converged = converged;
+        self.reason = reason;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Basis, Polynomial};
+
+    // cubic through noise-free samples, recovered exactly by a 4-term fit
+    fn cubic() -> (Polynomial, Dataset) {
+        let data = Dataset::grid(|x| 1.0 - 2.0 * x + 0.5 * x * x * x, -1.0, 0.1, 21);
+        (Polynomial::new(4), data)
+    }
+
+    fn assert_fit(report: &SolveReport) {
+        let c = to_array(&report.coeffs);
+        for (k, want) in [1.0, -2.0, 0.0, 0.5].into_iter().enumerate() {
+            assert!((c[k] - want).abs() < 1e-6, "c{} = {} ({})", k, c[k], report.reason);
+        }
+    }
+
+    #[test]
+    fn gauss_newton_solves_a_linear_fit_in_one_step() {
+        let (model, data) = cubic();
+        let report = gauss_newton(&model, &data, &Params::zero(), &SolveOptions::default());
+        assert!(report.converged, "{}", report.reason);
+        assert!(report.history.len() <= 2);
+        assert_fit(&report);
+    }
+
+    #[test]
+    fn gauss_newton_without_decrease_has_not_converged() {
+        // from the optimum with no tolerances left, no step can decrease
+        let (model, data) = cubic();
+        let data = Dataset::new(data.x.clone(), data.x.iter().map(|x| x.cos()).collect());
+        let opts = SolveOptions { max_iter: 50, gtol: 0.0, ftol: 0.0 };
+        let start = gauss_newton(&model, &data, &Params::zero(), &SolveOptions::default()).coeffs;
+        let report = gauss_newton(&model, &data, &start, &opts);
+        assert!(!report.converged);
+        assert!(report.reason == "no decrease along the Gauss-Newton step" || report.reason == "iteration limit");
+    }
+
+    #[test]
+    fn levenberg_marquardt_fits_a_chebyshev_basis() {
+        let data = Dataset::grid(|x| x.exp(), 0.0, 0.05, 41);
+        let model = Polynomial::with_basis(8, Basis::Chebyshev, (0.0, 2.0));
+        let report = levenberg_marquardt(&model, &data, &Params::zero(), &SolveOptions::default());
+        assert!(report.converged, "{}", report.reason);
+        assert!(report.loss < 1e-10);
+        assert!(report.damping.is_some());
+    }
+
+    #[test]
+    fn lbfgs_minimizes_a_rosenbrock_valley() {
+        let f = |c: &Params| {
+            let c = to_array(c);
+            (1.0 - c[0]).powi(2) + 100.0 * (c[1] - c[0] * c[0]).powi(2)
+        };
+        let grad = |c: &Params| {
+            let c = to_array(c);
+            let mut g = [0.0; N];
+            g[0] = -2.0 * (1.0 - c[0]) - 400.0 * c[0] * (c[1] - c[0] * c[0]);
+            g[1] = 200.0 * (c[1] - c[0] * c[0]);
+            from_array(g)
+        };
+        let opts = SolveOptions { max_iter: 500, ..Default::default() };
+        let report = lbfgs(f, grad, &Params::zero(), 8, &LineSearch::strong_wolfe(), &opts);
+        assert!(report.converged, "{}", report.reason);
+        let c = to_array(&report.coeffs);
+        assert!((c[0] - 1.0).abs() < 1e-5 && (c[1] - 1.0).abs() < 1e-5);
+    }
+}
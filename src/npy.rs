@@ -0,0 +1,237 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use crate::data::Dataset;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// A dense array read from a `.npy` file, stored row-major as `f64`.
+#[derive(Clone, Debug)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+impl NpyArray {
+    /// Column `j` of a 2-D array, or the whole array if it is 1-D.
+    pub fn column(&self, j: usize) -> Option<Vec<f64>> {
+        match self.shape.as_slice() {
+            [_] if j == 0 => Some(self.data.clone()),
+            [rows, cols] if j < *cols => Some((0..*rows).map(|i| self.data[i * cols + j]).collect()),
+            _ => None,
+        }
+    }
+}
+
+fn invalid(path: &str, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, msg))
+}
+
+// value of `'key': value` in the header dict, up to the next top-level comma
+fn header_field<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}']).unwrap_or(rest.len())
+    };
+    Some(rest[..end].trim())
+}
+
+/// Read a little or big endian `f4`/`f8` array of any version.
+pub fn read_npy(path: &str) -> io::Result<NpyArray> {
+    let bytes = fs::read(path)?;
+    if bytes.len() < 10 || &bytes[..6] != MAGIC {
+        return Err(invalid(path, "not a .npy file"));
+    }
+
+    let (header_len, offset) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12),
+        _ => return Err(invalid(path, "unsupported .npy version")),
+    };
+    let header = std::str::from_utf8(bytes.get(offset..offset + header_len).ok_or_else(|| invalid(path, "truncated header"))?)
+        .map_err(|_| invalid(path, "header is not utf-8"))?;
+
+    let descr = header_field(header, "descr").ok_or_else(|| invalid(path, "missing descr"))?.trim_matches('\'');
+    let fortran = header_field(header, "fortran_order") == Some("True");
+    let shape: Vec<usize> = header_field(header, "shape")
+        .ok_or_else(|| invalid(path, "missing shape"))?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.trim().parse().map_err(|_| invalid(path, "bad shape")))
+        .collect::<io::Result<_>>()?;
+
+    let (big, size) = match descr {
+        "<f8" | "=f8" | "|f8" => (false, 8),
+        ">f8" => (true, 8),
+        "<f4" | "=f4" | "|f4" => (false, 4),
+        ">f4" => (true, 4),
+        d => return Err(invalid(path, &format!("unsupported dtype {}", d))),
+    };
+
+    let count: usize = shape.iter().product();
+    let body = &bytes[offset + header_len..];
+    if body.len() < count * size {
+        return Err(invalid(path, "truncated data"));
+    }
+
+    let mut data: Vec<f64> = body
+        .chunks_exact(size)
+        .take(count)
+        .map(|c| match (size, big) {
+            (8, false) => f64::from_le_bytes(c.try_into().unwrap()),
+            (8, true) => f64::from_be_bytes(c.try_into().unwrap()),
+            (_, false) => f32::from_le_bytes(c.try_into().unwrap()) as f64,
+            (_, true) => f32::from_be_bytes(c.try_into().unwrap()) as f64,
+        })
+        .collect();
+
+    // store everything row-major
+    if fortran && shape.len() == 2 {
+        let (rows, cols) = (shape[0], shape[1]);
+        let mut t = vec![0.0; count];
+        for i in 0..rows {
+            for j in 0..cols {
+                t[i * cols + j] = data[j * rows + i];
+            }
+        }
+        data = t;
+    }
+
+    Ok(NpyArray { shape, data })
+}
+
+/// Write a row-major `<f8` array.
+pub fn write_npy(path: &str, shape: &[usize], data: &[f64]) -> io::Result<()> {
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    let shape_str = if dims.len() == 1 { format!("({},)", dims[0]) } else { format!("({})", dims.join(", ")) };
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape_str);
+
+    // pad so the data starts on a 64 byte boundary, header ends with '\n'
+    let total = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - total % 64) % 64));
+    header.push('\n');
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&[1, 0])?;
+    out.write_all(&(header.len() as u16).to_le_bytes())?;
+    out.write_all(header.as_bytes())?;
+    for v in data {
+        out.write_all(&v.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+/// Load a dataset from columns `x_col` and `y_col` of a 2-D array. Rows
+/// with a NaN in either column are dropped.
+pub fn read_dataset(path: &str, x_col: usize, y_col: usize) -> io::Result<Dataset> {
+    let a = read_npy(path)?;
+    let x = a.column(x_col).ok_or_else(|| invalid(path, "x column out of range"))?;
+    let y = a.column(y_col).ok_or_else(|| invalid(path, "y column out of range"))?;
+
+    let mut data = Dataset::default();
+    for (x, y) in x.into_iter().zip(y) {
+        if !x.is_nan() && !y.is_nan() {
+            data.x.push(x);
+            data.y.push(y);
+        }
+    }
+    Ok(data)
+}
+
+/// Dump an `(n, 4)` array of `x, y, prediction, residual` rows.
+pub fn write_predictions<F>(path: &str, data: &Dataset, predict: F) -> io::Result<()>
+where
+    F: Fn(f64) -> f64,
+{
+    let mut rows = Vec::with_capacity(data.len() * 4);
+    for (x, y) in data.iter() {
+        let p = predict(x);
+        rows.extend_from_slice(&[x, y, p, p - y]);
+    }
+    write_npy(path, &[data.len(), 4], &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(name: &str) -> String {
+        std::env::temp_dir().join(format!("slut-ml-npy-{}-{}.npy", std::process::id(), name)).to_str().unwrap().to_string()
+    }
+
+    // a version 1 file with the given header dict and raw body
+    fn raw(name: &str, dict: &str, body: &[u8]) -> String {
+        let p = path(name);
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(dict.len() as u16 + 1).to_le_bytes());
+        bytes.extend_from_slice(dict.as_bytes());
+        bytes.push(b'\n');
+        bytes.extend_from_slice(body);
+        fs::write(&p, bytes).unwrap();
+        p
+    }
+
+    #[test]
+    fn round_trip() {
+        let p = path("round");
+        let data = [1.0, 2.0, 3.5, -4.0, 5.0, f64::NAN];
+        write_npy(&p, &[3, 2], &data).unwrap();
+        let a = read_npy(&p).unwrap();
+        assert_eq!(a.shape, [3, 2]);
+        assert_eq!(a.column(0).unwrap(), [1.0, 3.5, 5.0]);
+        assert!(a.column(2).is_none());
+        // the NaN row is dropped
+        let d = read_dataset(&p, 0, 1).unwrap();
+        assert_eq!((d.x, d.y), (vec![1.0, 3.5], vec![2.0, -4.0]));
+        // data starts on a 64 byte boundary
+        assert_eq!((fs::read(&p).unwrap().len() - data.len() * 8) % 64, 0);
+    }
+
+    #[test]
+    fn one_dimensional() {
+        let p = path("flat");
+        write_npy(&p, &[3], &[1.0, 2.0, 3.0]).unwrap();
+        let a = read_npy(&p).unwrap();
+        assert_eq!(a.shape, [3]);
+        assert_eq!(a.column(0).unwrap(), [1.0, 2.0, 3.0]);
+        assert!(a.column(1).is_none());
+    }
+
+    #[test]
+    fn fortran_order_is_transposed() {
+        let body: Vec<u8> = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let p = raw("fortran", "{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }", &body);
+        let a = read_npy(&p).unwrap();
+        assert_eq!(a.data, [1.0, 3.0, 5.0, 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn big_endian_f4() {
+        let body: Vec<u8> = [0.5f32, -2.0].iter().flat_map(|v| v.to_be_bytes()).collect();
+        let p = raw("f4", "{'descr': '>f4', 'fortran_order': False, 'shape': (2,), }", &body);
+        assert_eq!(read_npy(&p).unwrap().data, [0.5, -2.0]);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let body = 1.0f64.to_le_bytes();
+        let cases = [
+            ("dtype", raw("int", "{'descr': '<i8', 'fortran_order': False, 'shape': (1,), }", &body), "unsupported dtype"),
+            ("short", raw("short", "{'descr': '<f8', 'fortran_order': False, 'shape': (2,), }", &body), "truncated data"),
+            ("shape", raw("noshape", "{'descr': '<f8', 'fortran_order': False, }", &body), "missing shape"),
+        ];
+        for (what, p, msg) in cases {
+            let e = read_npy(&p).unwrap_err().to_string();
+            assert!(e.contains(msg), "{}: {}", what, e);
+        }
+        let p = path("magic");
+        fs::write(&p, b"PK\x03\x04 not numpy").unwrap();
+        assert!(read_npy(&p).unwrap_err().to_string().contains("not a .npy file"));
+    }
+}
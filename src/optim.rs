@@ -0,0 +1,306 @@
+use slut::{dimension::Dimensionless, tensor::*};
+
+use crate::{from_array, to_array, N, Params};
+
+/// Updates a parameter vector from its gradient.
+///
+/// Implementations keep whatever per-parameter state they need between
+/// calls; `reset` clears it, e.g. when a new term is unlocked.
+pub trait Optimizer: Send {
+    fn step(
+        &mut self,
+        params: &mut Params,
+        grads: &Params,
+        lr: Scalar<f64, Dimensionless>,
+    );
+
+    fn reset(&mut self) {}
+
+    fn name(&self) -> &'static str;
+}
+
+/// Look up an optimizer with its default settings.
+pub fn by_name(name: &str) -> Option<Box<dyn Optimizer>> {
+    let o: Box<dyn Optimizer> = match name {
+        "sgd" => Box::new(Sgd),
+        "momentum" => Box::new(Momentum::new(0.9, false)),
+        "nesterov" => Box::new(Momentum::new(0.9, true)),
+        "rmsprop" => Box::new(RmsProp::new(0.9)),
+        "adagrad" => Box::new(AdaGrad::new()),
+        "adam" => Box::new(Adam::new(0.9, 0.999)),
+        "adamw" => Box::new(Adam::adamw(0.9, 0.999, 1e-2)),
+        _ => return None,
+    };
+    Some(o)
+}
+
+const EPS: f64 = 1e-8;
+
+/// Plain gradient descent, `p ← p - lr·g`.
+pub struct Sgd;
+
+impl Optimizer for Sgd {
+    fn step(
+        &mut self,
+        params: &mut Params,
+        grads: &Params,
+        lr: Scalar<f64, Dimensionless>,
+    ) {
+        *params -= *grads * lr;
+    }
+
+    fn name(&self) -> &'static str {
+        "sgd"
+    }
+}
+
+/// Heavy-ball momentum, optionally with the Nesterov look-ahead.
+pub struct Momentum {
+    pub beta: f64,
+    pub nesterov: bool,
+    velocity: [f64; N],
+}
+
+impl Momentum {
+    pub fn new(beta: f64, nesterov: bool) -> Self {
+        Momentum { beta, nesterov, velocity: [0.0; N] }
+    }
+}
+
+impl Optimizer for Momentum {
+    fn step(
+        &mut self,
+        params: &mut Params,
+        grads: &Params,
+        lr: Scalar<f64, Dimensionless>,
+    ) {
+        let mut p = to_array(params);
+        let g = to_array(grads);
+        let lr = lr.raw();
+
+        for k in 0..N {
+            self.velocity[k] = self.beta * self.velocity[k] + g[k];
+            let d = if self.nesterov {
+                g[k] + self.beta * self.velocity[k]
+            } else {
+                self.velocity[k]
+            };
+            p[k] -= lr * d;
+        }
+        *params = from_array(p);
+    }
+
+    fn reset(&mut self) {
+        self.velocity = [0.0; N];
+    }
+
+    fn name(&self) -> &'static str {
+        if self.nesterov { "nesterov" } else { "momentum" }
+    }
+}
+
+/// Divides the step by a running RMS of past gradients.
+pub struct RmsProp {
+    pub rho: f64,
+    sq: [f64; N],
+}
+
+impl RmsProp {
+    pub fn new(rho: f64) -> Self {
+        RmsProp { rho, sq: [0.0; N] }
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn step(
+        &mut self,
+        params: &mut Params,
+        grads: &Params,
+        lr: Scalar<f64, Dimensionless>,
+    ) {
+        let mut p = to_array(params);
+        let g = to_array(grads);
+        let lr = lr.raw();
+
+        for k in 0..N {
+            self.sq[k] = self.rho * self.sq[k] + (1.0 - self.rho) * g[k] * g[k];
+            p[k] -= lr * g[k] / (self.sq[k].sqrt() + EPS);
+        }
+        *params = from_array(p);
+    }
+
+    fn reset(&mut self) {
+        self.sq = [0.0; N];
+    }
+
+    fn name(&self) -> &'static str {
+        "rmsprop"
+    }
+}
+
+/// Divides the step by the root of all squared gradients seen so far.
+pub struct AdaGrad {
+    sum: [f64; N],
+}
+
+impl AdaGrad {
+    pub fn new() -> Self {
+        AdaGrad { sum: [0.0; N] }
+    }
+}
+
+impl Default for AdaGrad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Optimizer for AdaGrad {
+    fn step(
+        &mut self,
+        params: &mut Params,
+        grads: &Params,
+        lr: Scalar<f64, Dimensionless>,
+    ) {
+        let mut p = to_array(params);
+        let g = to_array(grads);
+        let lr = lr.raw();
+
+        for k in 0..N {
+            self.sum[k] += g[k] * g[k];
+            p[k] -= lr * g[k] / (self.sum[k].sqrt() + EPS);
+        }
+        *params = from_array(p);
+    }
+
+    fn reset(&mut self) {
+        self.sum = [0.0; N];
+    }
+
+    fn name(&self) -> &'static str {
+        "adagrad"
+    }
+}
+
+/// Adam with bias-corrected moments.
+///
+/// `weight_decay` is added to the gradient (L2) for plain Adam, and applied
+/// directly to the parameters when `decoupled` is set (AdamW).
+pub struct Adam {
+    pub beta1: f64,
+    pub beta2: f64,
+    pub weight_decay: f64,
+    pub decoupled: bool,
+    m: [f64; N],
+    v: [f64; N],
+    t: i32,
+}
+
+impl Adam {
+    pub fn new(beta1: f64, beta2: f64) -> Self {
+        Adam { beta1, beta2, weight_decay: 0.0, decoupled: false, m: [0.0; N], v: [0.0; N], t: 0 }
+    }
+
+    pub fn adamw(beta1: f64, beta2: f64, weight_decay: f64) -> Self {
+        Adam { weight_decay, decoupled: true, ..Adam::new(beta1, beta2) }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(
+        &mut self,
+        params: &mut Params,
+        grads: &Params,
+        lr: Scalar<f64, Dimensionless>,
+    ) {
+        let mut p = to_array(params);
+        let g = to_array(grads);
+        let lr = lr.raw();
+
+        self.t += 1;
+        let c1 = 1.0 - self.beta1.powi(self.t);
+        let c2 = 1.0 - self.beta2.powi(self.t);
+
+        for k in 0..N {
+            let gk = if self.decoupled { g[k] } else { g[k] + self.weight_decay * p[k] };
+            self.m[k] = self.beta1 * self.m[k] + (1.0 - self.beta1) * gk;
+            self.v[k] = self.beta2 * self.v[k] + (1.0 - self.beta2) * gk * gk;
+
+            let m_hat = self.m[k] / c1;
+            let v_hat = self.v[k] / c2;
+            if self.decoupled {
+                p[k] -= lr * self.weight_decay * p[k];
+            }
+            p[k] -= lr * m_hat / (v_hat.sqrt() + EPS);
+        }
+        *params = from_array(p);
+    }
+
+    fn reset(&mut self) {
+        self.m = [0.0; N];
+        self.v = [0.0; N];
+        self.t = 0;
+    }
+
+    fn name(&self) -> &'static str {
+        if self.decoupled { "adamw" } else { "adam" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slut::{dimension, dless, units};
+
+    use super::*;
+
+    const NAMES: [&str; 7] = ["sgd", "momentum", "nesterov", "rmsprop", "adagrad", "adam", "adamw"];
+
+    fn grads(scale: f64) -> Params {
+        from_array(std::array::from_fn(|k| scale * (k as f64 - 4.5)))
+    }
+
+    fn run(o: &mut dyn Optimizer, steps: usize) -> [f64; N] {
+        let mut p = Params::zero();
+        for i in 0..steps {
+            o.step(&mut p, &grads(1.0 + i as f64), dless!(0.1));
+        }
+        to_array(&p)
+    }
+
+    #[test]
+    fn reset_forgets_the_history() {
+        for name in NAMES {
+            let mut used = by_name(name).unwrap();
+            run(used.as_mut(), 5);
+            used.reset();
+            assert_eq!(run(used.as_mut(), 3), run(by_name(name).unwrap().as_mut(), 3), "{}", name);
+        }
+    }
+
+    #[test]
+    fn every_optimizer_descends() {
+        for name in NAMES {
+            let p = run(by_name(name).unwrap().as_mut(), 1);
+            let g = to_array(&grads(1.0));
+            assert!(p.iter().zip(&g).all(|(p, g)| p * g < 0.0), "{}: {:?}", name, p);
+        }
+    }
+
+    #[test]
+    fn adam_first_step_is_the_learning_rate() {
+        // bias correction makes the first step lr·sign(g) up to EPS
+        let p = run(&mut Adam::new(0.9, 0.999), 1);
+        assert!(p.iter().all(|p| (p.abs() - 0.1).abs() < 1e-6));
+    }
+
+    #[test]
+    fn momentum_accumulates() {
+        let mut o = Momentum::new(0.5, false);
+        let mut p = Params::zero();
+        let g = from_array([1.0; N]);
+        o.step(&mut p, &g, dless!(1.0));
+        o.step(&mut p, &g, dless!(1.0));
+        // 1 + (0.5 + 1)
+        assert_eq!(to_array(&p)[0], -2.5);
+    }
+}
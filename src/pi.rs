@@ -0,0 +1,460 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::csv::{read_rows, split_header};
+use crate::data::Dataset;
+use crate::dims::{parse_unit, superscript, Dim, UnitSpec};
+
+/// A named, dimensioned input of a physical relation.
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub name: String,
+    pub dim: Dim,
+}
+
+impl Variable {
+    pub fn new(name: &str, dim: Dim) -> Self {
+        Variable { name: name.to_string(), dim }
+    }
+}
+
+/// The target cannot be made dimensionless from the inputs.
+#[derive(Clone, Debug)]
+pub struct PiError {
+    pub target: String,
+    pub dim: Dim,
+}
+
+impl fmt::Display for PiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no product of the inputs has the dimension of {} ({})", self.target, self.dim)
+    }
+}
+
+impl Error for PiError {}
+
+/// Buckingham π analysis of `target = f(inputs)`.
+///
+/// The dimension matrix has one column per input and one row per base
+/// dimension. Every vector `e` of its nullspace gives a dimensionless group
+/// `Π x_j^e_j`, and a basis of `n - rank` such groups can replace the `n`
+/// inputs. The target is scaled by a product of the inputs of its own
+/// dimension, so the relation becomes `π_y = F(π_1, …, π_k)`.
+#[derive(Clone, Debug)]
+pub struct PiAnalysis {
+    pub inputs: Vec<Variable>,
+    pub target: Variable,
+    /// Integer exponents of the inputs in each group.
+    pub groups: Vec<Vec<i64>>,
+    /// Exponents of the inputs in the target's scale, possibly fractional
+    /// as in `√(L/g)`.
+    pub target_exponents: Vec<f64>,
+}
+
+impl PiAnalysis {
+    pub fn new(inputs: Vec<Variable>, target: Variable) -> Result<Self, PiError> {
+        let n = inputs.len();
+        // 7 × (n + 1), the target's dimension as the augmented column
+        let mut m: Vec<Vec<Frac>> = (0..7)
+            .map(|i| {
+                let mut row: Vec<Frac> = inputs.iter().map(|v| Frac::from(v.dim.0[i] as i64)).collect();
+                row.push(Frac::from(target.dim.0[i] as i64));
+                row
+            })
+            .collect();
+        let pivots = rref(&mut m, n);
+
+        // a pivot-free row with a nonzero right-hand side is inconsistent
+        if m.iter().skip(pivots.len()).any(|row| !row[n].is_zero()) {
+            return Err(PiError { target: target.name.clone(), dim: target.dim });
+        }
+
+        // particular solution with every free exponent at 0
+        let mut target_exponents = vec![0.0; n];
+        for (r, &p) in pivots.iter().enumerate() {
+            target_exponents[p] = m[r][n].to_f64();
+        }
+
+        // one group per free column, that column's exponent set to 1
+        let mut groups = Vec::new();
+        for free in (0..n).filter(|j| !pivots.contains(j)) {
+            let mut e = vec![Frac::from(0); n];
+            e[free] = Frac::from(1);
+            for (r, &p) in pivots.iter().enumerate() {
+                e[p] = -m[r][free];
+            }
+            groups.push(to_integers(&e));
+        }
+
+        Ok(PiAnalysis { inputs, target, groups, target_exponents })
+    }
+
+    /// `π_i` for one sample of the inputs, in the order of `inputs`.
+    pub fn group_values(&self, row: &[f64]) -> Vec<f64> {
+        self.groups.iter().map(|e| product(row, e.iter().map(|&k| k as f64))).collect()
+    }
+
+    /// The product of inputs `π_y = y / scale` is measured against.
+    pub fn target_scale(&self, row: &[f64]) -> f64 {
+        product(row, self.target_exponents.iter().copied())
+    }
+
+    /// e.g. `ρ·v·L·μ⁻¹`
+    pub fn group_name(&self, i: usize) -> String {
+        let parts: Vec<String> = self.groups[i]
+            .iter()
+            .zip(&self.inputs)
+            .filter(|(e, _)| **e != 0)
+            .map(|(&e, v)| if e == 1 { v.name.clone() } else { format!("{}{}", v.name, superscript(e as i32)) })
+            .collect();
+        if parts.is_empty() { "1".to_string() } else { parts.join("·") }
+    }
+
+    /// One-dimensional dataset `(π_group, π_y)` for the `Model`s, both
+    /// dimensionless. With a single group this is the whole relation.
+    pub fn dataset(&self, rows: &[Vec<f64>], y: &[f64], group: usize) -> Dataset {
+        let x = rows.iter().map(|r| self.group_values(r)[group]).collect();
+        let t = rows.iter().zip(y).map(|(r, y)| y / self.target_scale(r)).collect();
+        Dataset::new(x, t)
+    }
+
+    /// Fit the scaling law `π_y = C Π π_i^a_i` by least squares in log
+    /// space, `k + 1` parameters for `k` groups. Samples with a
+    /// non-positive group or target are skipped.
+    pub fn fit_power_law(&self, rows: &[Vec<f64>], y: &[f64]) -> Option<PowerLaw> {
+        let k = self.groups.len();
+        // normal equations of ln π_y = ln C + Σ a_i ln π_i
+        let mut ata = vec![vec![0.0; k + 1]; k + 1];
+        let mut atb = vec![0.0; k + 1];
+        let mut used = 0;
+        for (r, &y) in rows.iter().zip(y) {
+            let t = y / self.target_scale(r);
+            let pis = self.group_values(r);
+            if t <= 0.0 || pis.iter().any(|&p| p <= 0.0) {
+                continue;
+            }
+            let mut f = vec![1.0];
+            f.extend(pis.iter().map(|p| p.ln()));
+            for (i, row) in ata.iter_mut().enumerate() {
+                for (j, a) in row.iter_mut().enumerate() {
+                    *a += f[i] * f[j];
+                }
+                atb[i] += f[i] * t.ln();
+            }
+            used += 1;
+        }
+        if used <= k {
+            return None;
+        }
+
+        let sol = solve(ata, atb)?;
+        Some(PowerLaw { coeff: sol[0].exp(), exponents: sol[1..].to_vec() })
+    }
+}
+
+/// Samples of dimensioned inputs and a target.
+#[derive(Clone, Debug)]
+pub struct Table {
+    pub inputs: Vec<Variable>,
+    pub target: Variable,
+    /// Input values of each sample, in SI units.
+    pub rows: Vec<Vec<f64>>,
+    pub y: Vec<f64>,
+}
+
+/// Read a table from a CSV file whose last column is the target. Headers
+/// like `L[m]` name each column and give its unit; values are converted to
+/// canonical SI units.
+pub fn read_csv(path: &str) -> io::Result<Table> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, msg));
+    let (header, rows) = read_rows(path, ',', Some(true))?;
+    let header = header.ok_or_else(|| invalid("no header".to_string()))?;
+    if header.len() < 2 {
+        return Err(invalid("need at least one input and a target column".to_string()));
+    }
+
+    let mut vars = Vec::with_capacity(header.len());
+    let mut units = Vec::with_capacity(header.len());
+    for field in &header {
+        let (name, unit) = split_header(field);
+        let unit = match unit {
+            Some(u) => parse_unit(u).map_err(|e| invalid(format!("column {}: {}", field, e)))?,
+            None => UnitSpec { scale: 1.0, offset: 0.0, dim: Dim::NONE },
+        };
+        vars.push(Variable::new(name, unit.dim));
+        units.push(unit);
+    }
+
+    let mut samples = Vec::with_capacity(rows.len());
+    let mut y = Vec::with_capacity(rows.len());
+    for (n, row) in rows.iter().enumerate() {
+        if row.len() != header.len() {
+            return Err(invalid(format!("row {}: {} fields, expected {}", n + 1, row.len(), header.len())));
+        }
+        let mut values = Vec::with_capacity(row.len());
+        for (field, unit) in row.iter().zip(&units) {
+            let v: f64 = field.parse().map_err(|e| invalid(format!("row {}: {}", n + 1, e)))?;
+            values.push(unit.to_si(v));
+        }
+        y.push(values.pop().unwrap());
+        samples.push(values);
+    }
+    let target = vars.pop().unwrap();
+    Ok(Table { inputs: vars, target, rows: samples, y })
+}
+
+/// `π_y = coeff · Π π_i^exponents_i`
+#[derive(Clone, Debug)]
+pub struct PowerLaw {
+    pub coeff: f64,
+    pub exponents: Vec<f64>,
+}
+
+impl PowerLaw {
+    /// Predicted target, back in the target's dimension.
+    pub fn predict(&self, pi: &PiAnalysis, row: &[f64]) -> f64 {
+        let groups = pi.group_values(row);
+        self.coeff * product(&groups, self.exponents.iter().copied()) * pi.target_scale(row)
+    }
+}
+
+fn product(values: &[f64], exponents: impl Iterator<Item = f64>) -> f64 {
+    values.iter().zip(exponents).map(|(v, e)| v.powf(e)).product()
+}
+
+// Gaussian elimination with partial pivoting, None when singular
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    // a NaN entry is never picked as pivot
+    let size = |v: f64| if v.is_nan() { -1.0 } else { v.abs() };
+    for c in 0..n {
+        let p = (c..n).max_by(|&i, &j| size(a[i][c]).total_cmp(&size(a[j][c])))?;
+        if size(a[p][c]) < 1e-12 {
+            return None;
+        }
+        a.swap(c, p);
+        b.swap(c, p);
+        let (top, rest) = a.split_at_mut(c + 1);
+        let pivot = &top[c];
+        for (r, row) in rest.iter_mut().enumerate() {
+            let f = row[c] / pivot[c];
+            for (v, p) in row[c..n].iter_mut().zip(&pivot[c..n]) {
+                *v -= f * p;
+            }
+            b[c + 1 + r] -= f * b[c];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for r in (0..n).rev() {
+        let s: f64 = (r + 1..n).map(|j| a[r][j] * x[j]).sum();
+        x[r] = (b[r] - s) / a[r][r];
+    }
+    Some(x)
+}
+
+// Reduced row echelon form over the first `cols` columns, exact. Returns
+// the pivot column of each leading row.
+fn rref(m: &mut [Vec<Frac>], cols: usize) -> Vec<usize> {
+    let mut pivots = Vec::new();
+    let mut row = 0;
+    for c in 0..cols {
+        let Some(p) = (row..m.len()).find(|&r| !m[r][c].is_zero()) else {
+            continue;
+        };
+        m.swap(row, p);
+        let lead = m[row][c];
+        for v in m[row].iter_mut() {
+            *v = *v / lead;
+        }
+        for r in 0..m.len() {
+            if r != row && !m[r][c].is_zero() {
+                let f = m[r][c];
+                for j in 0..m[r].len() {
+                    let d = f * m[row][j];
+                    m[r][j] = m[r][j] - d;
+                }
+            }
+        }
+        pivots.push(c);
+        row += 1;
+    }
+    pivots
+}
+
+// Smallest integer multiple of a rational vector
+fn to_integers(e: &[Frac]) -> Vec<i64> {
+    let l = e.iter().fold(1, |l, f| l / gcd(l, f.den) * f.den);
+    let ints: Vec<i64> = e.iter().map(|f| f.num * (l / f.den)).collect();
+    let g = ints.iter().fold(0, |g, &v| gcd(g, v.abs()));
+    ints.iter().map(|v| v / g.max(1)).collect()
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+// Exact rational, denominator always positive
+#[derive(Clone, Copy, Debug)]
+struct Frac {
+    num: i64,
+    den: i64,
+}
+
+impl Frac {
+    fn new(num: i64, den: i64) -> Self {
+        let g = gcd(num, den).max(1) * den.signum();
+        Frac { num: num / g, den: den / g }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl From<i64> for Frac {
+    fn from(n: i64) -> Self {
+        Frac { num: n, den: 1 }
+    }
+}
+
+impl std::ops::Sub for Frac {
+    type Output = Frac;
+
+    fn sub(self, rhs: Frac) -> Frac {
+        Frac::new(self.num * rhs.den - rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl std::ops::Mul for Frac {
+    type Output = Frac;
+
+    fn mul(self, rhs: Frac) -> Frac {
+        Frac::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl std::ops::Div for Frac {
+    type Output = Frac;
+
+    fn div(self, rhs: Frac) -> Frac {
+        Frac::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+impl std::ops::Neg for Frac {
+    type Output = Frac;
+
+    fn neg(self) -> Frac {
+        Frac { num: -self.num, den: self.den }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use slut::dimension::{Length, Mass, Time};
+    use slut::si::{Acceleration, Force};
+
+    use super::*;
+    use crate::dims::Dimensional;
+
+    fn frac(num: i64, den: i64) -> Frac {
+        Frac::new(num, den)
+    }
+
+    // period of a pendulum of length L, mass m and amplitude θ under gravity g
+    fn pendulum() -> PiAnalysis {
+        let inputs = vec![
+            Variable::new("L", Length::DIM),
+            Variable::new("g", Acceleration::DIM),
+            Variable::new("m", Mass::DIM),
+            Variable::new("θ", Dim::NONE),
+        ];
+        PiAnalysis::new(inputs, Variable::new("T", Time::DIM)).unwrap()
+    }
+
+    #[test]
+    fn pendulum_has_one_group() {
+        let pi = pendulum();
+        // only the amplitude is dimensionless, the mass cannot be cancelled
+        assert_eq!(pi.groups, [[0, 0, 0, 1]]);
+        assert_eq!(pi.group_name(0), "θ");
+        assert_eq!(pi.target_exponents, [0.5, -0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn pendulum_scaling_law() {
+        let pi = pendulum();
+        let rows: Vec<Vec<f64>> = (1..20).map(|i| vec![0.1 * i as f64, 9.81 + 0.1 * (i % 3) as f64, 1.0 + i as f64, 0.01 * i as f64]).collect();
+        let y: Vec<f64> = rows.iter().map(|r| 2.0 * PI * (r[0] / r[1]).sqrt()).collect();
+        let law = pi.fit_power_law(&rows, &y).unwrap();
+        assert!((law.coeff - 2.0 * PI).abs() < 1e-9);
+        assert!(law.exponents[0].abs() < 1e-9);
+        assert!((law.predict(&pi, &rows[3]) - y[3]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reynolds_number() {
+        // drag force over density ρ, speed v, length L and viscosity μ
+        let (l, m, t) = (Length::DIM, Mass::DIM, Time::DIM);
+        let inputs = vec![
+            Variable::new("ρ", m.quotient(l.powi(3))),
+            Variable::new("v", l.quotient(t)),
+            Variable::new("L", l),
+            Variable::new("μ", m.quotient(l.product(t))),
+        ];
+        let force = Force::DIM;
+        let pi = PiAnalysis::new(inputs, Variable::new("F", force)).unwrap();
+        assert_eq!(pi.groups.len(), 1);
+        let g = &pi.groups[0];
+        // ρvL/μ up to sign
+        assert!(*g == [-1, -1, -1, 1] || *g == [1, 1, 1, -1], "{:?}", g);
+        assert_eq!(pi.target_exponents, [1.0, 2.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn unreachable_target() {
+        let inputs = vec![Variable::new("L", Length::DIM)];
+        let err = PiAnalysis::new(inputs, Variable::new("m", Mass::DIM)).unwrap_err();
+        assert_eq!(err.dim, Mass::DIM);
+    }
+
+    #[test]
+    fn rref_is_exact() {
+        let mut m: Vec<Vec<Frac>> = [[2, 4, 1], [3, 6, 2], [0, 0, 0]]
+            .iter()
+            .map(|r| r.iter().map(|&v| Frac::from(v)).collect())
+            .collect();
+        assert_eq!(rref(&mut m, 3), [0, 2]);
+        let want = [[1, 2, 0], [0, 0, 1], [0, 0, 0]];
+        for (row, want) in m.iter().zip(want) {
+            for (v, w) in row.iter().zip(want) {
+                assert!((*v - Frac::from(w)).is_zero(), "{:?}", m);
+            }
+        }
+    }
+
+    #[test]
+    fn integer_exponents() {
+        assert_eq!(to_integers(&[frac(1, 2), frac(-1, 3), frac(0, 1)]), [3, -2, 0]);
+        assert_eq!(to_integers(&[frac(4, 1), frac(-2, 1)]), [2, -1]);
+    }
+
+    #[test]
+    fn reads_a_table() {
+        let path = std::env::temp_dir().join(format!("slut-ml-pi-table-{}.csv", std::process::id()));
+        std::fs::write(&path, "L[cm],g[m/s^2],T[ms]\n100,9.81,2006\n").unwrap();
+        let t = read_csv(path.to_str().unwrap()).unwrap();
+        assert_eq!(t.inputs[0].name, "L");
+        assert_eq!(t.inputs[1].dim, Acceleration::DIM);
+        assert_eq!(t.target.dim, Time::DIM);
+        assert_eq!(t.rows, [[1.0, 9.81]]);
+        assert!((t.y[0] - 2.006).abs() < 1e-12);
+    }
+}
@@ -1,10 +1,7 @@
 use std::fs::File;
 use std::io::Write;
 
-use slut::dimension::Dimensionless;
-use slut::tensor::Vector;
-
-use crate::N;
+use crate::data::Dataset;
 
 pub fn plot_comparison<F, T>(
     trained_fn: F,
@@ -35,6 +32,35 @@ where
         target_data.push(target_y);
     }
     
+    write_comparison(&x_values, &trained_data, &target_data, output_file)
+}
+
+/// Same chart as `plot_comparison`, with the dataset samples as the target.
+pub fn plot_data<F>(
+    trained_fn: F,
+    data: &Dataset,
+    output_file: &str,
+) -> std::io::Result<()>
+where
+    F: Fn(f64) -> f64,
+{
+    // Sort the samples so the chart reads left to right
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&a, &b| data.x[a].total_cmp(&data.x[b]));
+
+    let x_values: Vec<f64> = order.iter().map(|&i| data.x[i]).collect();
+    let target_data: Vec<f64> = order.iter().map(|&i| data.y[i]).collect();
+    let trained_data: Vec<f64> = x_values.iter().map(|&x| trained_fn(x)).collect();
+
+    write_comparison(&x_values, &trained_data, &target_data, output_file)
+}
+
+fn write_comparison(
+    x_values: &[f64],
+    trained_data: &[f64],
+    target_data: &[f64],
+    output_file: &str,
+) -> std::io::Result<()> {
     // Create HTML with embedded Chart.js
     let html_content = format!(r#"
 <!DOCTYPE html>
@@ -186,12 +212,33 @@ where
     Ok(())
 }
 
+/// `extra` series (e.g. validation loss) are drawn on the loss chart next to
+/// the training loss, one per `(label, values)` pair.
 pub fn loss_curve(
     losses: &[f64],
+    extra: &[(&str, &[f64])],
     output_file: &str,
     threshold: Option<f64>,
 ) -> std::io::Result<()> {
     let epochs: Vec<usize> = (0..losses.len()).collect();
+
+    // Extra datasets for the loss chart
+    let colors = ["255, 159, 64", "153, 102, 255", "201, 203, 207"];
+    let extra_datasets: String = extra.iter().enumerate().map(|(i, (label, values))| {
+        let color = colors[i % colors.len()];
+        format!(r#",
+                    {{
+                        label: '{label}',
+                        data: {values:?},
+                        borderColor: 'rgb({color})',
+                        backgroundColor: 'rgba({color}, 0.1)',
+                        borderWidth: 2,
+                        fill: false,
+                        pointRadius: 1,
+                        pointHoverRadius: 4,
+                        tension: 0.1
+                    }}"#)
+    }).collect();
     
     // Calculate approximate derivative (gradient) of loss
     let mut loss_derivatives = Vec::new();
@@ -203,7 +250,7 @@ pub fn loss_curve(
     
     // Remove outliers from derivatives using IQR method
     let mut sorted_derivatives = loss_derivatives.clone();
-    sorted_derivatives.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted_derivatives.sort_by(f64::total_cmp);
     
     let q1_idx = sorted_derivatives.len() / 4;
     let q3_idx = 3 * sorted_derivatives.len() / 4;
@@ -356,7 +403,7 @@ pub fn loss_curve(
                         pointRadius: 1,
                         pointHoverRadius: 4,
                         tension: 0.1
-                    }}
+                    }}{extra_datasets}
                 ]
             }},
             options: {{
@@ -458,7 +505,7 @@ pub fn loss_curve(
     </script>
 </body>
 </html>
-"#, epochs = epochs, losses = losses, epochs_count = losses.len(), 
+"#, epochs = epochs, losses = losses, epochs_count = losses.len(), extra_datasets = extra_datasets,
     stable_epochs = stable_epochs, stable_derivatives = stable_derivatives,
     filtered_derivatives = filtered_derivatives, loss_derivatives = loss_derivatives);
 
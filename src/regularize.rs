@@ -0,0 +1,197 @@
+use slut::{dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+use crate::{from_array, to_array, N, Params};
+
+/// A penalty on the parameters added to the training objective.
+///
+/// The smooth part goes through `grad` like the data loss. Non-smooth parts
+/// (L1) are applied after each optimizer step through `prox`, the proximal
+/// operator for a step of size `lr`. Solvers that only see the objective and
+/// its gradient use `subgradient`, which covers both parts.
+pub trait Regularizer: Send {
+    fn penalty(&self, params: &Params) -> Scalar<f64, Dimensionless>;
+
+    fn grad(&self, _params: &Params) -> Params {
+        Params::zero()
+    }
+
+    /// A subgradient of the whole `penalty`, `grad` for smooth penalties.
+    fn subgradient(&self, params: &Params) -> Params {
+        self.grad(params)
+    }
+
+    fn prox(&self, params: &Params, _lr: Scalar<f64, Dimensionless>) -> Params {
+        *params
+    }
+
+    fn name(&self) -> &'static str;
+}
+
+/// Ridge, `λ Σ c_k²`.
+pub struct L2 {
+    pub lambda: f64,
+}
+
+impl Regularizer for L2 {
+    fn penalty(&self, params: &Params) -> Scalar<f64, Dimensionless> {
+        dless!(self.lambda * to_array(params).iter().map(|c| c * c).sum::<f64>())
+    }
+
+    fn grad(&self, params: &Params) -> Params {
+        *params * dless!(2.0 * self.lambda)
+    }
+
+    fn name(&self) -> &'static str {
+        "l2"
+    }
+}
+
+// soft thresholding, the proximal operator of t·|c|
+fn soft_threshold(params: &Params, t: f64) -> Params {
+    from_array(to_array(params).map(|c| c.signum() * (c.abs() - t).max(0.0)))
+}
+
+// λ·sign(c), the subgradient of λ·|c| taking 0 at the kink
+fn l1_subgradient(params: &Params, lambda: f64) -> [f64; N] {
+    to_array(params).map(|c| if c > 0.0 { lambda } else if c < 0.0 { -lambda } else { 0.0 })
+}
+
+/// Lasso, `λ Σ |c_k|`, applied by soft thresholding after each step.
+pub struct L1 {
+    pub lambda: f64,
+}
+
+impl Regularizer for L1 {
+    fn penalty(&self, params: &Params) -> Scalar<f64, Dimensionless> {
+        dless!(self.lambda * to_array(params).iter().map(|c| c.abs()).sum::<f64>())
+    }
+
+    fn subgradient(&self, params: &Params) -> Params {
+        from_array(l1_subgradient(params, self.lambda))
+    }
+
+    fn prox(&self, params: &Params, lr: Scalar<f64, Dimensionless>) -> Params {
+        soft_threshold(params, lr.raw() * self.lambda)
+    }
+
+    fn name(&self) -> &'static str {
+        "l1"
+    }
+}
+
+/// Elastic net, `l1 Σ |c_k| + l2 Σ c_k²`.
+pub struct ElasticNet {
+    pub l1: f64,
+    pub l2: f64,
+}
+
+impl Regularizer for ElasticNet {
+    fn penalty(&self, params: &Params) -> Scalar<f64, Dimensionless> {
+        let c = to_array(params);
+        dless!(self.l1 * c.iter().map(|c| c.abs()).sum::<f64>() + self.l2 * c.iter().map(|c| c * c).sum::<f64>())
+    }
+
+    fn grad(&self, params: &Params) -> Params {
+        *params * dless!(2.0 * self.l2)
+    }
+
+    fn subgradient(&self, params: &Params) -> Params {
+        let c = to_array(params);
+        let s = l1_subgradient(params, self.l1);
+        from_array(std::array::from_fn(|k| s[k] + 2.0 * self.l2 * c[k]))
+    }
+
+    fn prox(&self, params: &Params, lr: Scalar<f64, Dimensionless>) -> Params {
+        soft_threshold(params, lr.raw() * self.l1)
+    }
+
+    fn name(&self) -> &'static str {
+        "elastic_net"
+    }
+}
+
+/// `λ Σ k^power c_k²`, pushing the high-degree terms harder than the low
+/// ones.
+pub struct DegreeWeighted {
+    pub lambda: f64,
+    pub power: f64,
+}
+
+impl DegreeWeighted {
+    fn weight(&self, k: usize) -> f64 {
+        self.lambda * (k as f64).powf(self.power)
+    }
+}
+
+impl Regularizer for DegreeWeighted {
+    fn penalty(&self, params: &Params) -> Scalar<f64, Dimensionless> {
+        let c = to_array(params);
+        dless!((0..N).map(|k| self.weight(k) * c[k] * c[k]).sum::<f64>())
+    }
+
+    fn grad(&self, params: &Params) -> Params {
+        let c = to_array(params);
+        from_array(std::array::from_fn(|k| 2.0 * self.weight(k) * c[k]))
+    }
+
+    fn name(&self) -> &'static str {
+        "degree"
+    }
+}
+
+/// Look up a regularizer with its default strength.
+pub fn by_name(name: &str) -> Option<Box<dyn Regularizer>> {
+    let r: Box<dyn Regularizer> = match name {
+        "l2" => Box::new(L2 { lambda: 1e-4 }),
+        "l1" => Box::new(L1 { lambda: 1e-4 }),
+        "elastic_net" => Box::new(ElasticNet { l1: 1e-4, l2: 1e-4 }),
+        "degree" => Box::new(DegreeWeighted { lambda: 1e-6, power: 2.0 }),
+        _ => return None,
+    };
+    Some(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> Params {
+        from_array(std::array::from_fn(|k| [0.5, -2.0, 0.0][k % 3]))
+    }
+
+    // central differences of the penalty, away from the kinks
+    fn numeric(r: &dyn Regularizer, c: &Params, k: usize) -> f64 {
+        let h = 1e-6;
+        let (mut p, mut m) = (to_array(c), to_array(c));
+        p[k] += h;
+        m[k] -= h;
+        (r.penalty(&from_array(p)).raw() - r.penalty(&from_array(m)).raw()) / (2.0 * h)
+    }
+
+    #[test]
+    fn subgradient_matches_the_penalty() {
+        let c = params();
+        let regs: Vec<Box<dyn Regularizer>> = ["l2", "l1", "elastic_net", "degree"].iter().map(|n| by_name(n).unwrap()).collect();
+        for r in &regs {
+            let g = to_array(&r.subgradient(&c));
+            for k in (0..N).filter(|k| k % 3 != 2) {
+                assert!((g[k] - numeric(r.as_ref(), &c, k)).abs() < 1e-8, "{} c{}", r.name(), k);
+            }
+        }
+    }
+
+    #[test]
+    fn l1_subgradient_is_zero_at_the_kink() {
+        let l1 = L1 { lambda: 0.3 };
+        let g = to_array(&l1.subgradient(&params()));
+        assert_eq!(&g[..3], &[0.3, -0.3, 0.0]);
+        // the smooth part alone is empty, the prox applies it
+        assert_eq!(to_array(&l1.grad(&params())), [0.0; N]);
+    }
+
+    #[test]
+    fn prox_soft_thresholds() {
+        let c = to_array(&L1 { lambda: 1.0 }.prox(&params(), dless!(0.5)));
+        assert_eq!(&c[..3], &[0.0, -1.5, 0.0]);
+    }
+}
@@ -0,0 +1,193 @@
+use std::f64::consts::PI;
+
+use crate::model::{Basis, Model, Polynomial};
+use crate::{N, Params};
+
+/// Result of the Remez exchange.
+#[derive(Clone, Debug)]
+pub struct RemezFit {
+    /// Coefficients for `Polynomial::new(degree + 1)`.
+    pub coeffs: Params,
+    /// Levelled error `E` of the final iteration, `|E|` is the minimax error
+    /// once converged.
+    pub levelled_error: f64,
+    /// Largest absolute error found on the dense check grid.
+    pub max_error: f64,
+    /// Reference points where the error equioscillates.
+    pub nodes: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Best uniform polynomial approximation of `f` on `[a, b]`.
+///
+/// Starting from Chebyshev nodes, each iteration solves for the polynomial
+/// whose error alternates with equal magnitude on `degree + 2` reference
+/// points, then moves the references to the extrema of the new error curve.
+/// Stops once the extrema agree with the levelled error to within `tol`
+/// (relative). The systems are solved in the Chebyshev basis on `[a, b]`
+/// and the result converted to monomial form; `degree` must be below `N`.
+pub fn remez<F>(f: F, degree: usize, a: f64, b: f64, max_iter: usize, tol: f64) -> RemezFit
+where
+    F: Fn(f64) -> f64,
+{
+    let degree = degree.min(N - 1);
+    let n = degree + 2;
+    let model = Polynomial::with_basis(degree + 1, Basis::Chebyshev, (a, b));
+
+    // Chebyshev extrema as the initial reference
+    let mut nodes: Vec<f64> = (0..n)
+        .map(|i| {
+            let t = -(PI * i as f64 / (n - 1) as f64).cos();
+            0.5 * (a + b) + 0.5 * (b - a) * t
+        })
+        .collect();
+
+    // dense grid used to locate error extrema
+    let grid_len = 200 * n;
+    let grid: Vec<f64> = (0..=grid_len).map(|i| a + (b - a) * i as f64 / grid_len as f64).collect();
+
+    let mut coeffs = [0.0f64; N];
+    let mut e = 0.0;
+    let mut max_error = f64::INFINITY;
+    let mut iterations = 0;
+    let mut converged = false;
+
+    for it in 0..max_iter {
+        iterations = it + 1;
+        let (c, lev) = solve_reference(&f, &model, &nodes, degree);
+        coeffs = c;
+        e = lev;
+
+        let p = |x: f64| model.forward(&crate::from_array(coeffs), x).raw();
+        let err: Vec<f64> = grid.iter().map(|&x| f(x) - p(x)).collect();
+
+        // keep the largest |err| point in each run of equal sign
+        let mut extrema: Vec<(f64, f64)> = Vec::new();
+        for (&x, &r) in grid.iter().zip(&err) {
+            match extrema.last_mut() {
+                Some(last) if last.1.signum() == r.signum() => {
+                    if r.abs() > last.1.abs() {
+                        *last = (x, r);
+                    }
+                }
+                _ => extrema.push((x, r)),
+            }
+        }
+
+        // trim to n alternating points, dropping the smallest at the ends
+        while extrema.len() > n {
+            let first = extrema[0].1.abs();
+            let last = extrema[extrema.len() - 1].1.abs();
+            if first < last {
+                extrema.remove(0);
+            } else {
+                extrema.pop();
+            }
+        }
+
+        max_error = err.iter().fold(0.0f64, |acc, r| acc.max(r.abs()));
+        if extrema.len() < n {
+            // degenerate error curve, keep the last reference
+            break;
+        }
+        nodes = extrema.iter().map(|p| p.0).collect();
+
+        if max_error - e.abs() <= tol * e.abs().max(f64::MIN_POSITIVE) {
+            converged = true;
+            break;
+        }
+    }
+
+    RemezFit {
+        coeffs: model.to_monomial(&crate::from_array(coeffs)),
+        levelled_error: e,
+        max_error,
+        nodes,
+        iterations,
+        converged,
+    }
+}
+
+// Solve Σ c_k p_k(x_i) + (-1)^i E = f(x_i) for the coefficients and E.
+fn solve_reference<F>(f: &F, model: &Polynomial, nodes: &[f64], degree: usize) -> ([f64; N], f64)
+where
+    F: Fn(f64) -> f64,
+{
+    let n = nodes.len();
+    let mut m: Vec<Vec<f64>> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let p = crate::to_array(&model.features(x).unwrap());
+            let mut row = p[..=degree].to_vec();
+            row.push(if i % 2 == 0 { 1.0 } else { -1.0 });
+            row.push(f(x));
+            row
+        })
+        .collect();
+
+    // Gaussian elimination with partial pivoting
+    // a NaN entry is never picked as pivot
+    let size = |v: f64| if v.is_nan() { -1.0 } else { v.abs() };
+    for col in 0..n {
+        let piv = (col..n).max_by(|&i, &j| size(m[i][col]).total_cmp(&size(m[j][col]))).unwrap();
+        m.swap(col, piv);
+        let (top, rest) = m.split_at_mut(col + 1);
+        let pivot = &top[col];
+        for row in rest.iter_mut() {
+            let r = row[col] / pivot[col];
+            for (v, p) in row[col..=n].iter_mut().zip(&pivot[col..=n]) {
+                *v -= r * p;
+            }
+        }
+    }
+    let mut sol = vec![0.0f64; n];
+    for i in (0..n).rev() {
+        let s: f64 = (i + 1..n).map(|j| m[i][j] * sol[j]).sum();
+        sol[i] = (m[i][n] - s) / m[i][i];
+    }
+
+    let mut c = [0.0f64; N];
+    c[..=degree].copy_from_slice(&sol[..=degree]);
+    (c, sol[n - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_array;
+
+    #[test]
+    fn line_through_a_parabola() {
+        // best line to x² on [0, 1] is x - ⅛, error ⅛
+        let fit = remez(|x| x * x, 1, 0.0, 1.0, 20, 1e-9);
+        assert!(fit.converged);
+        let c = to_array(&fit.coeffs);
+        assert!((c[0] + 0.125).abs() < 1e-9 && (c[1] - 1.0).abs() < 1e-9, "{:?}", &c[..2]);
+        assert!((fit.levelled_error.abs() - 0.125).abs() < 1e-9);
+        assert_eq!(fit.nodes.len(), 3);
+    }
+
+    #[test]
+    fn chebyshev_equioscillation() {
+        // x³ - ¾x = T₃/4 is the error of the best quadratic to x³ on [-1, 1]
+        let fit = remez(|x| x * x * x, 2, -1.0, 1.0, 20, 1e-9);
+        assert!(fit.converged);
+        let c = to_array(&fit.coeffs);
+        assert!(c[0].abs() < 1e-9 && (c[1] - 0.75).abs() < 1e-9 && c[2].abs() < 1e-9, "{:?}", &c[..3]);
+        assert!((fit.max_error - 0.25).abs() < 1e-6);
+        for (x, want) in fit.nodes.iter().zip([-1.0, -0.5, 0.5, 1.0]) {
+            assert!((x - want).abs() < 1e-2, "{:?}", fit.nodes);
+        }
+    }
+
+    #[test]
+    fn beats_least_squares_in_the_max_norm() {
+        let fit = remez(f64::exp, 3, -1.0, 1.0, 30, 1e-9);
+        assert!(fit.converged);
+        // error bound of the interpolant at the Chebyshev nodes, e/(2³·4!)
+        assert!(fit.max_error < std::f64::consts::E / 192.0);
+        assert!((fit.max_error - fit.levelled_error.abs()) < 1e-9 * fit.max_error);
+    }
+}
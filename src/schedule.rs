@@ -0,0 +1,368 @@
+use std::f64::consts::PI;
+
+use slut::{dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+/// Where training is when a scheduler is asked for a new rate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Progress {
+    /// Current epoch from 0, the one that just finished for schedules
+    /// asked once per epoch.
+    pub epoch: usize,
+    /// Optimizer steps taken so far, over all epochs.
+    pub step: usize,
+    /// Optimizer steps per epoch, 1 for full-batch training.
+    pub steps_per_epoch: usize,
+}
+
+impl Progress {
+    /// Epochs done so far counted in steps, fractional between epoch ends.
+    pub fn epochs(&self) -> f64 {
+        self.step as f64 / self.steps_per_epoch.max(1) as f64
+    }
+}
+
+/// Decides the learning rate for the next optimizer step, or the next
+/// epoch.
+///
+/// Schedules that only depend on time are asked after every optimizer step,
+/// those driven by the loss once per epoch, see `per_step`. `lr` is the
+/// rate used so far and `loss` the training loss at the end of the last
+/// epoch.
+pub trait LrScheduler: Send {
+    fn step(
+        &mut self,
+        p: &Progress,
+        lr: Scalar<f64, Dimensionless>,
+        loss: Scalar<f64, Dimensionless>,
+    ) -> Scalar<f64, Dimensionless>;
+
+    /// Whether `step` is called after every optimizer step rather than at
+    /// the end of each epoch.
+    fn per_step(&self) -> bool {
+        false
+    }
+
+    /// Schedulers tied to the curriculum's convergence threshold override this.
+    fn set_threshold(&mut self, _threshold: f64) {}
+
+    fn name(&self) -> &'static str;
+}
+
+/// Look up a scheduler with its default settings.
+pub fn by_name(name: &str, base_lr: f64) -> Option<Box<dyn LrScheduler>> {
+    let s: Box<dyn LrScheduler> = match name {
+        "constant" => Box::new(Constant),
+        "step" => Box::new(StepDecay { base_lr, gamma: 0.5, step_size: 1000 }),
+        "exp" => Box::new(Exponential { base_lr, gamma: 0.999 }),
+        "cosine" => Box::new(CosineRestarts::new(base_lr, 1e-6, 500, 2)),
+        "warmup" => Box::new(Warmup::new(base_lr, 100, Box::new(Constant))),
+        "plateau" => Box::new(ReduceOnPlateau::new(0.5, 50, 1e-7)),
+        "adaptive" => Box::new(Adaptive::new(-5e-5)),
+        _ => return None,
+    };
+    Some(s)
+}
+
+/// Keeps the learning rate unchanged.
+pub struct Constant;
+
+impl LrScheduler for Constant {
+    fn step(&mut self, _p: &Progress, lr: Scalar<f64, Dimensionless>, _loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        lr
+    }
+
+    fn per_step(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "constant"
+    }
+}
+
+/// Multiplies the base rate by `gamma` every `step_size` epochs.
+pub struct StepDecay {
+    pub base_lr: f64,
+    pub gamma: f64,
+    pub step_size: usize,
+}
+
+impl LrScheduler for StepDecay {
+    fn step(&mut self, p: &Progress, _lr: Scalar<f64, Dimensionless>, _loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.base_lr * self.gamma.powf((p.epochs() / self.step_size as f64).floor()))
+    }
+
+    fn per_step(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "step"
+    }
+}
+
+/// `base_lr · gamma^epoch`, with fractional epochs between epoch ends
+pub struct Exponential {
+    pub base_lr: f64,
+    pub gamma: f64,
+}
+
+impl LrScheduler for Exponential {
+    fn step(&mut self, p: &Progress, _lr: Scalar<f64, Dimensionless>, _loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        dless!(self.base_lr * self.gamma.powf(p.epochs()))
+    }
+
+    fn per_step(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "exp"
+    }
+}
+
+/// Cosine annealing with warm restarts (SGDR). Each cycle is `mult` times
+/// longer than the previous one.
+pub struct CosineRestarts {
+    pub base_lr: f64,
+    pub min_lr: f64,
+    pub period: usize,
+    pub mult: usize,
+}
+
+impl CosineRestarts {
+    pub fn new(base_lr: f64, min_lr: f64, period: usize, mult: usize) -> Self {
+        CosineRestarts { base_lr, min_lr, period, mult }
+    }
+}
+
+impl LrScheduler for CosineRestarts {
+    fn step(&mut self, p: &Progress, _lr: Scalar<f64, Dimensionless>, _loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        // find the position inside the current cycle
+        let mut t = p.epochs();
+        let mut len = self.period.max(1) as f64;
+        while t >= len {
+            t -= len;
+            len *= self.mult.max(1) as f64;
+        }
+        let cos = (PI * t / len).cos();
+        dless!(self.min_lr + 0.5 * (self.base_lr - self.min_lr) * (1.0 + cos))
+    }
+
+    fn per_step(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "cosine"
+    }
+}
+
+/// Ramps linearly up to `base_lr` over `steps` epochs, then hands over to
+/// `inner` with the epoch count restarted. Runs per step when `inner` does.
+pub struct Warmup {
+    pub base_lr: f64,
+    pub steps: usize,
+    inner: Box<dyn LrScheduler>,
+}
+
+impl Warmup {
+    pub fn new(base_lr: f64, steps: usize, inner: Box<dyn LrScheduler>) -> Self {
+        Warmup { base_lr, steps, inner }
+    }
+}
+
+impl LrScheduler for Warmup {
+    fn step(&mut self, p: &Progress, lr: Scalar<f64, Dimensionless>, loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        let warm = self.steps as f64;
+        if p.epochs() <= warm {
+            dless!(self.base_lr * ((p.epochs() + 1.0) / warm).min(1.0))
+        } else {
+            let restarted = Progress {
+                epoch: p.epoch.saturating_sub(self.steps),
+                step: p.step - self.steps * p.steps_per_epoch.max(1),
+                ..*p
+            };
+            self.inner.step(&restarted, lr, loss)
+        }
+    }
+
+    fn per_step(&self) -> bool {
+        self.inner.per_step()
+    }
+
+    fn set_threshold(&mut self, threshold: f64) {
+        self.inner.set_threshold(threshold);
+    }
+
+    fn name(&self) -> &'static str {
+        "warmup"
+    }
+}
+
+/// Multiplies the rate by `factor` when the loss has not improved by more
+/// than `rel_threshold` (relative) for `patience` epochs.
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub min_lr: f64,
+    pub rel_threshold: f64,
+    best: f64,
+    bad_epochs: usize,
+}
+
+impl ReduceOnPlateau {
+    pub fn new(factor: f64, patience: usize, min_lr: f64) -> Self {
+        ReduceOnPlateau { factor, patience, min_lr, rel_threshold: 1e-4, best: f64::INFINITY, bad_epochs: 0 }
+    }
+}
+
+impl LrScheduler for ReduceOnPlateau {
+    fn step(&mut self, _p: &Progress, lr: Scalar<f64, Dimensionless>, loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        let l = loss.raw();
+        if l < self.best * (1.0 - self.rel_threshold) {
+            self.best = l;
+            self.bad_epochs = 0;
+            return lr;
+        }
+
+        self.bad_epochs += 1;
+        if self.bad_epochs > self.patience {
+            self.bad_epochs = 0;
+            return dless!((lr.raw() * self.factor).max(self.min_lr));
+        }
+        lr
+    }
+
+    fn name(&self) -> &'static str {
+        "plateau"
+    }
+}
+
+/// The original loss-delta heuristic from the training loop.
+///
+/// The rate shrinks by `decay` whenever the loss goes up. Once past `warmup`
+/// epochs, a decrease grows it by `1 + (2·threshold + |dl|)·gain`, which
+/// shrinks it instead while the improvement is smaller than `2·|threshold|`.
+/// Exceeding `max_lr` snaps the rate to `reset_lr` (set it to `max_lr` to
+/// clamp instead), and it never goes under `min_lr`.
+pub struct Adaptive {
+    pub decay: f64,
+    pub gain: f64,
+    pub warmup: usize,
+    pub min_lr: f64,
+    pub max_lr: f64,
+    pub reset_lr: f64,
+    pub threshold: f64,
+    last: f64,
+}
+
+impl Adaptive {
+    pub fn new(threshold: f64) -> Self {
+        Adaptive {
+            decay: 0.99,
+            gain: 20.0,
+            warmup: 50,
+            min_lr: 1e-6,
+            max_lr: 1e-3,
+            reset_lr: 1e-6,
+            threshold,
+            last: 0.0,
+        }
+    }
+}
+
+impl LrScheduler for Adaptive {
+    fn step(&mut self, p: &Progress, lr: Scalar<f64, Dimensionless>, loss: Scalar<f64, Dimensionless>) -> Scalar<f64, Dimensionless> {
+        let dl = loss.raw() - self.last;
+        self.last = loss.raw();
+
+        let mut lr = lr.raw();
+        if dl > 0.0 {
+            lr *= self.decay;
+        } else if dl < 0.0 && p.epoch > self.warmup {
+            lr *= 1.0 + (2.0 * self.threshold + dl.abs()) * self.gain;
+            if lr > self.max_lr {
+                lr = self.reset_lr;
+            } else if lr < self.min_lr {
+                lr = self.min_lr;
+            }
+        }
+        dless!(lr)
+    }
+
+    fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
+    fn name(&self) -> &'static str {
+        "adaptive"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &mut dyn LrScheduler, step: usize, steps_per_epoch: usize) -> f64 {
+        let p = Progress { epoch: step / steps_per_epoch, step, steps_per_epoch };
+        s.step(&p, dless!(1.0), dless!(0.0)).raw()
+    }
+
+    #[test]
+    fn step_decay_counts_optimizer_steps() {
+        let mut s = StepDecay { base_lr: 1.0, gamma: 0.5, step_size: 2 };
+        assert_eq!(at(&mut s, 7, 4), 1.0);
+        assert_eq!(at(&mut s, 8, 4), 0.5);
+        assert_eq!(at(&mut s, 16, 4), 0.25);
+        // full batch, one step per epoch
+        assert_eq!(at(&mut s, 2, 1), 0.5);
+    }
+
+    #[test]
+    fn exponential_decays_within_an_epoch() {
+        let mut s = Exponential { base_lr: 1.0, gamma: 0.25 };
+        assert!((at(&mut s, 2, 4) - 0.5).abs() < 1e-15);
+        assert!((at(&mut s, 4, 4) - 0.25).abs() < 1e-15);
+    }
+
+    #[test]
+    fn cosine_restarts_follow_the_cycles() {
+        let mut s = CosineRestarts::new(1.0, 0.0, 2, 2);
+        assert!((at(&mut s, 0, 10) - 1.0).abs() < 1e-15);
+        // halfway through the first cycle of 2 epochs
+        assert!((at(&mut s, 10, 10) - 0.5).abs() < 1e-15);
+        // restart, then a cycle of 4 epochs
+        assert!((at(&mut s, 20, 10) - 1.0).abs() < 1e-15);
+        assert!((at(&mut s, 40, 10) - 0.5).abs() < 1e-15);
+    }
+
+    #[test]
+    fn warmup_ramps_then_hands_over() {
+        let mut s = Warmup::new(1.0, 4, Box::new(StepDecay { base_lr: 1.0, gamma: 0.5, step_size: 1 }));
+        assert!(s.per_step());
+        assert_eq!(at(&mut s, 0, 2), 0.25);
+        assert_eq!(at(&mut s, 2, 2), 0.5);
+        assert_eq!(at(&mut s, 8, 2), 1.0);
+        // one epoch after the warmup
+        assert_eq!(at(&mut s, 10, 2), 0.5);
+        assert!(!Warmup::new(1.0, 4, Box::new(Adaptive::new(-5e-5))).per_step());
+    }
+
+    #[test]
+    fn loss_driven_schedules_run_per_epoch() {
+        assert!(!Adaptive::new(-5e-5).per_step());
+        assert!(!ReduceOnPlateau::new(0.5, 2, 0.0).per_step());
+    }
+
+    #[test]
+    fn plateau_reduces_after_patience() {
+        let mut s = ReduceOnPlateau::new(0.5, 1, 0.1);
+        let p = Progress::default();
+        let mut lr = dless!(1.0);
+        for _ in 0..3 {
+            lr = s.step(&p, lr, dless!(1.0));
+        }
+        assert_eq!(lr.raw(), 0.5);
+    }
+}
@@ -0,0 +1,822 @@
+use std::marker::PhantomData;
+
+use slut::{complex::c64, dimension::{self, Dimensionless}, dless, tensor::*, units};
+
+use crate::autodiff::Tape;
+use crate::complex::{self, ComplexParams};
+use crate::curriculum::Curriculum;
+use crate::data::{Batcher, Dataset};
+use crate::derivative_free;
+use crate::dims::{self, Dim, DimError, Dimensional, LossGrad, Lr, Monomial, PerPower, TrainingDims};
+use crate::dual::{self, Dual, DualParams};
+use crate::grad::{finite_diff, loss_grad, residuals};
+use crate::gradcheck::{self, GradCheck, GradCheckError, Tolerance};
+use crate::linesearch::{dot, LineSearch};
+use crate::loss::{Loss, Mse};
+use crate::lstsq::{self, LstsqFit, Method};
+use crate::model::Model;
+use crate::newton::{self, SolveOptions, SolveReport, Solver};
+use crate::optim::Optimizer;
+use crate::regularize::Regularizer;
+use crate::plot::{loss_curve, plot_data};
+use crate::schedule::{LrScheduler, Progress};
+use crate::{from_array, to_array, Params};
+
+#[derive(Clone, Copy, Debug)]
+pub enum GradMode {
+    // closed-form gradient from the model's param_grad
+    Analytic,
+    // reverse-mode through the model, one backward pass
+    Autodiff,
+    // forward-mode dual numbers, one pass per coefficient
+    Dual,
+    // numerical differentiation, also the fallback when the model does not
+    // support the selected mode
+    FiniteDiff,
+}
+
+impl GradMode {
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "analytic" => Some(GradMode::Analytic),
+            "autodiff" => Some(GradMode::Autodiff),
+            "dual" => Some(GradMode::Dual),
+            "fd" => Some(GradMode::FiniteDiff),
+            _ => None,
+        }
+    }
+}
+
+/// Stop when the validation loss has not improved by `min_delta` for
+/// `patience` epochs, then restore the best parameters seen. A run that
+/// reaches its last epoch keeps its final parameters.
+#[derive(Clone, Copy, Debug)]
+pub struct EarlyStopping<L: Dimensional = Dimensionless> {
+    pub patience: usize,
+    pub min_delta: Scalar<f64, L>,
+}
+
+/// Gradient-descent training of a `Model` on a `Dataset`.
+///
+/// Everything the loop needs lives here, so several trainers can run in the
+/// same process, or on different threads, without interfering.
+///
+/// `Y` is the slut dimension of the targets and `L` that of the loss values,
+/// `[Y]^degree` for the `Loss::degree` of `loss`. Both are checked against
+/// the data and the loss at runtime by `check_dims`. The learning rate and
+/// the clipping threshold follow from them, see `TrainingDims`. They are
+/// typed for the constant term: coefficient `k` is in `[Y]/[X]^k`, so its
+/// gradient is `[X]^k` times, and its step size `[X]^-2k` times, theirs.
+pub struct Trainer<Y = Dimensionless, L: Dimensional = Dimensionless>
+where
+    Y: TrainingDims<L>,
+{
+    pub model: Box<dyn Model>,
+    pub data: Dataset,
+    // held-out samples for validation loss, early stopping and the
+    // validation curriculum
+    pub val: Option<Dataset>,
+    pub coeffs: Params,
+    pub lr: Lr<Y, L>,
+    // gradient norm above which steps are clipped
+    pub max_gnorm: LossGrad<Y, L>,
+    pub epochs: usize,
+    // mini-batch iteration, full batch when None
+    pub batcher: Option<Batcher>,
+    // optimizer steps taken so far
+    pub steps: usize,
+    // step length by line search along the optimizer's direction instead
+    // of a fixed learning rate
+    pub line_search: Option<LineSearch>,
+    // last accepted line search step, where the next search starts
+    pub last_step: f64,
+    // objective and gradient evaluations on the training set so far
+    pub evaluations: usize,
+    pub grad_evaluations: usize,
+    pub grad_mode: GradMode,
+    pub h: f64,
+    // data loss over the residuals, MSE by default
+    pub loss: Box<dyn Loss>,
+    pub optimizer: Box<dyn Optimizer>,
+    pub scheduler: Box<dyn LrScheduler>,
+    pub curriculum: Curriculum<L>,
+    // penalties added to the objective, all summed
+    pub regularizers: Vec<Box<dyn Regularizer>>,
+    pub losses: Vec<f64>,
+    pub reg_losses: Vec<f64>,
+    pub val_losses: Vec<f64>,
+    pub early_stopping: Option<EarlyStopping<L>>,
+    // best validation checkpoint as (epoch, loss, parameters)
+    pub best: Option<(usize, Scalar<f64, L>, Params)>,
+    pub verbose: bool,
+    // save the html plots every that many epochs, 0 to disable
+    pub plot_every: usize,
+    targets: PhantomData<Y>,
+}
+
+impl<Y: Dimensional + TrainingDims<L>, L: Dimensional> Trainer<Y, L> {
+    pub fn new(
+        model: Box<dyn Model>,
+        data: Dataset,
+        optimizer: Box<dyn Optimizer>,
+        mut scheduler: Box<dyn LrScheduler>,
+        mut curriculum: Curriculum<L>,
+    ) -> Self {
+        curriculum.max_terms = model.param_count();
+        if let Some(t) = curriculum.threshold() {
+            scheduler.set_threshold(t.raw());
+        }
+        let coeffs = model.init_params();
+        Trainer {
+            model,
+            data,
+            val: None,
+            coeffs,
+            lr: Scalar::default([1e-4]),
+            max_gnorm: Scalar::default([10.0]),
+            epochs: 5000,
+            batcher: None,
+            steps: 0,
+            line_search: None,
+            last_step: 1.0,
+            evaluations: 0,
+            grad_evaluations: 0,
+            grad_mode: GradMode::Analytic,
+            h: 1e-6,
+            loss: Box::new(Mse),
+            optimizer,
+            scheduler,
+            curriculum,
+            regularizers: Vec::new(),
+            losses: Vec::new(),
+            reg_losses: Vec::new(),
+            val_losses: Vec::new(),
+            early_stopping: None,
+            best: None,
+            verbose: true,
+            plot_every: 100,
+            targets: PhantomData,
+        }
+    }
+
+    /// Closed-form least-squares fit on the training grid, as a baseline.
+    pub fn lstsq(&self, method: Method) -> Option<LstsqFit> {
+        lstsq::fit(self.model.as_ref(), &self.data, method)
+    }
+
+    /// Start from the least-squares solution instead of the model's initial
+    /// parameters. Every term is fitted already, so the curriculum is turned off.
+    pub fn seed_lstsq(&mut self, method: Method) -> Option<LstsqFit> {
+        let fit = self.lstsq(method)?;
+        self.coeffs = fit.coeffs;
+        self.curriculum = Curriculum::off();
+        Some(fit)
+    }
+
+    /// Dimension of each parameter for the dimensions of the training data.
+    pub fn coeff_dims(&self) -> Vec<Dim> {
+        self.model.coeff_dims(self.data.x_dim, self.data.y_dim)
+    }
+
+    /// `Err` when the targets are not in `Y`, the validation samples are in
+    /// other dimensions than the training samples, `L` is not the dimension
+    /// of the loss, or the loss has degree 0 and the targets are not
+    /// dimensionless.
+    pub fn check_dims(&self) -> Result<(), DimError> {
+        self.data.y_dim.expect(Y::DIM, "targets")?;
+        if let Some(v) = &self.val {
+            self.data.check_dims(v, "validation")?;
+        }
+        if self.loss.degree() == 0 {
+            Y::DIM.expect(Dim::NONE, &format!("{} loss targets", self.loss.name()))?;
+        }
+        L::DIM.expect(Y::DIM.powi(self.loss.degree()), &format!("{} loss", self.loss.name()))
+    }
+
+    /// A loss value with its unit.
+    pub fn format_loss(&self, l: Scalar<f64, L>) -> String {
+        dims::format_scalar(l)
+    }
+
+    /// Monomial coefficient `m_P` of the fit in `[Y]/[X]^P`, `None` when
+    /// the model is not a polynomial. `X` must match the dimension of the
+    /// training inputs.
+    pub fn monomial_coeff<X: Dimensional, const P: i32>(&self) -> Result<Option<Monomial<Y, X, P>>, DimError>
+    where
+        Y: PerPower<X, P>,
+    {
+        self.data.x_dim.expect(X::DIM, "inputs")?;
+        self.data.y_dim.expect(Y::DIM, "targets")?;
+        Ok(self.model.monomial(&self.coeffs).map(|m| Scalar::default([m.get_at(0, P as usize, 0).raw()])))
+    }
+
+    /// Data loss of `c` over the samples of `data`.
+    pub fn loss_on(&self, c: &Params, data: &Dataset) -> Scalar<f64, L> {
+        Scalar::default([self.loss.total(&residuals(self.model.as_ref(), c, data))])
+    }
+
+    // Compute the data loss over all training points
+    pub fn data_loss(&self, c: &Params) -> Scalar<f64, L> {
+        self.loss_on(c, &self.data)
+    }
+
+    /// Total regularization penalty, its strengths taken to be in the
+    /// dimension of the loss.
+    pub fn penalty(&self, c: &Params) -> Scalar<f64, L> {
+        let mut total = Scalar::<f64, Dimensionless>::zero();
+        for r in &self.regularizers {
+            total += r.penalty(c);
+        }
+        Scalar::default([total.raw()])
+    }
+
+    /// What training minimizes: the data loss plus the penalties.
+    pub fn objective(&self, c: &Params) -> Scalar<f64, L> {
+        self.data_loss(c) + self.penalty(c)
+    }
+
+    // Apply the optimizer, or search along its direction, then the proximal
+    // part of the regularizers with the step length actually taken.
+    fn apply(&mut self, grads: &Params) {
+        let lr = match self.line_search {
+            None => {
+                self.optimizer.step(&mut self.coeffs, grads, dless!(self.lr.raw()));
+                self.lr.raw()
+            }
+            Some(ls) => self.line_step(&ls, grads),
+        };
+        for r in &self.regularizers {
+            self.coeffs = r.prox(&self.coeffs, dless!(lr));
+        }
+        self.steps += 1;
+    }
+
+    // The optimizer's update at unit rate is the direction, its length is
+    // searched on the full training objective starting from the last
+    // accepted step, 1 at first. Returns the step taken, 0 if none was.
+    fn line_step(&mut self, ls: &LineSearch, grads: &Params) -> f64 {
+        let mut probe = self.coeffs;
+        self.optimizer.step(&mut probe, grads, dless!(1.0));
+        let (p, c) = (to_array(&probe), to_array(&self.coeffs));
+        let mut d: Params = from_array(std::array::from_fn(|k| p[k] - c[k]));
+
+        let f0 = self.objective(&self.coeffs).raw();
+        let g0 = self.objective_grad(&self.coeffs);
+        self.evaluations += 1;
+        self.grad_evaluations += 1;
+        if dot(&to_array(&g0), &to_array(&d)) >= 0.0 {
+            // not a descent direction for the full objective
+            d = from_array(to_array(grads).map(|g| -g));
+        }
+
+        let step = ls.search(
+            |c: &Params| self.objective(c).raw(),
+            |c: &Params| self.objective_grad(c),
+            &self.coeffs,
+            &d,
+            f0,
+            &g0,
+            self.last_step,
+        );
+        self.evaluations += step.evaluations;
+        self.grad_evaluations += step.grad_evaluations;
+        if self.verbose && !step.success {
+            println!("Line search gave up after {} evaluations, step {:e}", step.evaluations, step.t);
+        }
+        if step.t > 0.0 {
+            self.last_step = step.t;
+        }
+        self.coeffs = step.coeffs;
+        step.t
+    }
+
+    // Data loss on the held-out samples, if any
+    pub fn val_loss(&self, c: &Params) -> Option<Scalar<f64, L>> {
+        self.val.as_ref().map(|v| self.loss_on(c, v))
+    }
+
+    // Model and loss both on dual coefficients, for forward-mode derivatives
+    fn loss_dual(&self, c: &DualParams, data: &Dataset) -> Option<Dual<Dimensionless>> {
+        let mut r = Vec::with_capacity(data.len());
+
+        for (x, t) in data.iter() {
+            r.push(self.model.forward_dual(c, x)? - Dual::constant(dless!(t)));
+        }
+        Some(self.loss.total_dual(&r))
+    }
+
+    // Model and loss both on complex coefficients, for complex-step derivatives
+    fn loss_complex(&self, c: &ComplexParams, data: &Dataset) -> Option<c64> {
+        let mut r = Vec::with_capacity(data.len());
+
+        for (x, t) in data.iter() {
+            r.push(self.model.forward_complex(c, x)? - t);
+        }
+        Some(self.loss.total_complex(&r))
+    }
+
+    // Model and loss both on the tape, one backward pass
+    fn grad_autodiff(&self, c: &Params, data: &Dataset) -> Option<Params> {
+        if data.is_empty() {
+            return None;
+        }
+        let tape = Tape::new();
+        let cv = tape.vector(c);
+        let mut r = Vec::with_capacity(data.len());
+
+        for (x, t) in data.iter() {
+            r.push(self.model.forward_var(&cv, x)? - dless!(t));
+        }
+        Some(self.loss.total_var(&r).backward().wrt_vector(&cv))
+    }
+
+    fn grad_finite_diff(&self, c: &Params, data: &Dataset) -> Params {
+        let mut g = Params::zero();
+        for k in 0..self.model.param_count() {
+            g.set_at(0, k, 0, finite_diff(|c: &Params| dless!(self.loss_on(c, data).raw()), c, k, self.h));
+        }
+        g
+    }
+
+    /// Gradient of the data loss over the training set, in the loss
+    /// dimension per parameter.
+    pub fn grad(&self, c: &Params) -> Params {
+        self.grad_on(c, &self.data)
+    }
+
+    /// Gradient of the data loss over `data`, computed as selected by `grad_mode`.
+    pub fn grad_on(&self, c: &Params, data: &Dataset) -> Params {
+        let exact = match self.grad_mode {
+            GradMode::Analytic => {
+                loss_grad(self.model.as_ref(), c, data, self.loss.as_ref())
+            }
+            GradMode::Autodiff => self.grad_autodiff(c, data),
+            GradMode::Dual => {
+                // probe one point so models without dual support fall back cleanly
+                if !data.is_empty() && self.model.forward_dual(&dual::seed(c, c), 0.0).is_some() {
+                    Some(dual::gradient(|d: &DualParams| self.loss_dual(d, data).unwrap(), c))
+                } else {
+                    None
+                }
+            }
+            GradMode::FiniteDiff => None,
+        };
+        exact.unwrap_or_else(|| self.grad_finite_diff(c, data))
+    }
+
+    /// Check `grad`, in the current `grad_mode`, against central differences
+    /// of the data loss at every point, over the model's parameters, and
+    /// against complex-step derivatives when the model supports them.
+    pub fn gradcheck(&self, points: &[Params], tol: &Tolerance) -> Result<Vec<GradCheck>, GradCheckError> {
+        let n = self.model.param_count();
+        let central = gradcheck::gradcheck(
+            |c: &Params| self.grad(c),
+            |c: &Params| self.data_loss(c).raw(),
+            points,
+            n,
+            self.h,
+            tol,
+        );
+        // probe one point so models without complex support keep the central check only
+        if self.data.is_empty() || self.model.forward_complex(&complex::perturb(&self.coeffs, 0, 0.0), 0.0).is_none() {
+            return central;
+        }
+        let exact = gradcheck::gradcheck_complex(
+            |c: &Params| self.grad(c),
+            |p: &ComplexParams| self.loss_complex(p, &self.data).unwrap(),
+            points,
+            n,
+            gradcheck::Method::complex_step().step(),
+            tol,
+        );
+        gradcheck::merge(central, exact)
+    }
+
+    /// Gradient of the whole objective, penalties included, a subgradient
+    /// where an L1 term has a kink.
+    pub fn objective_grad(&self, c: &Params) -> Params {
+        let mut g = self.grad(c);
+        for r in &self.regularizers {
+            g += r.subgradient(c);
+        }
+        g
+    }
+
+    /// Fit with a second-order, quasi-Newton or derivative-free solver
+    /// instead of `train`.
+    ///
+    /// Gauss-Newton and Levenberg–Marquardt minimize the MSE of the
+    /// residuals, ignoring `loss` and the regularizers; the others minimize
+    /// the full `objective`. The loss history goes to `losses` for the plots.
+    pub fn solve(&mut self, solver: Solver, opts: &SolveOptions) -> SolveReport {
+        if let Err(e) = self.check_dims() {
+            panic!("{}", e);
+        }
+        let report = match solver {
+            Solver::GaussNewton => newton::gauss_newton(self.model.as_ref(), &self.data, &self.coeffs, opts),
+            Solver::LevenbergMarquardt => newton::levenberg_marquardt(self.model.as_ref(), &self.data, &self.coeffs, opts),
+            Solver::Lbfgs { memory } => newton::lbfgs(
+                |c: &Params| self.objective(c).raw(),
+                |c: &Params| self.objective_grad(c),
+                &self.coeffs,
+                memory,
+                &self.line_search.unwrap_or(LineSearch::strong_wolfe()),
+                opts,
+            ),
+            Solver::NelderMead => derivative_free::nelder_mead(
+                |c: &Params| self.objective(c).raw(),
+                &self.coeffs,
+                self.model.param_count(),
+                opts,
+            ),
+            Solver::CmaEs { sigma, seed } => derivative_free::cma_es(
+                |c: &Params| self.objective(c).raw(),
+                &self.coeffs,
+                self.model.param_count(),
+                sigma,
+                seed,
+                opts,
+            ),
+            Solver::Pso { particles, spread, seed } => derivative_free::pso(
+                |c: &Params| self.objective(c).raw(),
+                &self.coeffs,
+                self.model.param_count(),
+                particles,
+                spread,
+                seed,
+                opts,
+            ),
+        };
+        self.coeffs = report.coeffs;
+        self.evaluations += report.evaluations;
+        self.grad_evaluations += report.grad_evaluations;
+        self.losses.extend(&report.history);
+        if self.verbose {
+            println!("{}: {} iterations, {} evaluations, loss {:+e}, converged: {} ({})",
+                solver.name(), report.iterations, report.evaluations, report.loss, report.converged, report.reason);
+            if let Some(l) = report.damping {
+                println!("Final damping: {:e}", l);
+            }
+        }
+        report
+    }
+
+    // Scaled, masked and clipped gradient on one batch.
+    fn step_grads(&self, data: &Dataset) -> Params {
+        let mut grads = Params::zero();
+        let mut full = self.grad_on(&self.coeffs, data);
+        for r in &self.regularizers {
+            full += r.grad(&self.coeffs);
+        }
+
+        // Compute gradient for each coefficient, locked terms stay frozen
+        for k in 0..self.curriculum.enabled().min(self.model.param_count()) {
+            let g = full.get_at(0, k, 0);
+            let scale = self.model.grad_scale(k);
+            grads.set_at(0, k, 0, g * dless!(scale));
+        }
+
+        let g_norm = grads.norm();
+        let max_gnorm = dless!(self.max_gnorm.raw());
+
+        // Gradient clipping
+        if g_norm > max_gnorm {
+            if self.verbose {
+                println!("Gradient norm exceeded threshold, normalizing.");
+            }
+            grads = grads * (max_gnorm / g_norm);
+        }
+        grads
+    }
+
+    fn save_plots(&self, losses: &[f64]) {
+        let n = losses.len();
+        let val = &self.val_losses[..self.val_losses.len().min(n)];
+        let reg = &self.reg_losses[..self.reg_losses.len().min(n)];
+        loss_curve(losses, &[("Validation Loss", val), ("Regularization", reg)], "loss_curve.html", self.curriculum.threshold().map(|t| t.raw()))
+            .expect("Failed to create loss curve visualization");
+
+        let f = |x: f64| self.model.forward(&self.coeffs, x).raw();
+        plot_data(f, &self.data, "visualization.html")
+            .expect("Failed to create function comparison visualization");
+    }
+
+    /// Run the full training loop and return the final loss.
+    pub fn train(&mut self) -> Scalar<f64, L> {
+        if let Err(e) = self.check_dims() {
+            panic!("{}", e);
+        }
+        // a curriculum set after `new` still never unlocks unused parameters
+        self.curriculum.max_terms = self.curriculum.max_terms.min(self.model.param_count());
+        let mut l = Scalar::<f64, L>::zero();
+        let mut reg = Scalar::<f64, L>::zero();
+
+        let steps_per_epoch = self.batcher.as_ref().map_or(1, |b| b.steps_per_epoch(self.data.len()));
+        let per_step = self.scheduler.per_step();
+        let mut stopped = false;
+        // time schedules also set the rate of the very first step
+        if per_step {
+            self.schedule(0, steps_per_epoch, l + reg);
+        }
+
+        for e in 0..self.epochs {
+            let batches = self.batcher.as_mut().map(|b| b.batches(&self.data));
+            let grads = match batches {
+                Some(batches) => {
+                    let mut grads = Params::zero();
+                    for batch in batches {
+                        grads = self.step_grads(&batch);
+                        self.grad_evaluations += 1;
+                        self.apply(&grads);
+                        if per_step {
+                            self.schedule(e, steps_per_epoch, l + reg);
+                        }
+                    }
+                    grads
+                }
+                None => {
+                    let grads = self.step_grads(&self.data);
+                    self.grad_evaluations += 1;
+                    self.apply(&grads);
+                    if per_step {
+                        self.schedule(e, steps_per_epoch, l + reg);
+                    }
+                    grads
+                }
+            };
+
+            // Compute the data loss, penalties kept apart
+            let ln = self.data_loss(&self.coeffs);
+            self.evaluations += 1;
+            self.losses.push(l.raw());
+            self.reg_losses.push(reg.raw());
+            let dl = ln - l;
+            l = ln;
+            reg = self.penalty(&self.coeffs);
+
+            // loss-driven schedules set the rate for the next epoch
+            if !per_step {
+                self.schedule(e, steps_per_epoch, l + reg);
+            }
+
+            let val = self.val_loss(&self.coeffs);
+            if let Some(v) = val {
+                self.val_losses.push(v.raw());
+            }
+
+            if self.verbose && e % 5 == 0 {
+                println!("Gradient: {}", grads);
+                match val {
+                    Some(v) => println!("Epoch: {}, Step: {}, Loss: {:+e}, Reg: {:+e}, Val: {:+e}", e, self.steps, l.raw(), reg.raw(), v.raw()),
+                    None => println!("Epoch: {}, Step: {}, Loss: {:+e}, Reg: {:+e}", e, self.steps, l.raw(), reg.raw()),
+                }
+                println!("Glr: {}, Dl {}", self.lr.raw(), dl.raw().abs());
+            }
+            if let Some(u) = self.curriculum.update(e, l, dl, val) {
+                if self.verbose {
+                    println!("Converged at epoch {} with loss {} ({}), training {} terms", u.epoch, self.format_loss(u.loss), u.reason, u.terms);
+                }
+                // moment estimates of the old terms do not fit the new problem
+                self.optimizer.reset();
+            }
+
+            // Save visualizations every plot_every epochs
+            if self.plot_every > 0 && e % self.plot_every == 0 && e > 0 {
+                self.save_plots(&self.losses[0..=e]);
+                if self.verbose {
+                    println!("Saved visualizations for epoch {}", e);
+                }
+            }
+
+            if let Some(v) = val
+                && self.checkpoint(e, v) {
+                    stopped = true;
+                    break;
+                }
+        }
+
+        // Restore the best validation checkpoint when stopping early
+        if let (true, Some((epoch, _, coeffs))) = (stopped, &self.best) {
+            if self.verbose {
+                println!("Restoring best parameters from epoch {}", epoch);
+            }
+            self.coeffs = *coeffs;
+            l = self.data_loss(&self.coeffs);
+        }
+
+        l
+    }
+
+    // Ask the scheduler for the rate of the next step.
+    fn schedule(&mut self, epoch: usize, steps_per_epoch: usize, loss: Scalar<f64, L>) {
+        let progress = Progress { epoch, step: self.steps, steps_per_epoch };
+        self.lr = Scalar::default([self.scheduler.step(&progress, dless!(self.lr.raw()), dless!(loss.raw())).raw()]);
+    }
+
+    // Track the best validation loss, true when training should stop.
+    fn checkpoint(&mut self, epoch: usize, val: Scalar<f64, L>) -> bool {
+        let min_delta = self.early_stopping.map_or(Scalar::zero(), |s| s.min_delta);
+        match &self.best {
+            Some((_, best, _)) if val >= *best - min_delta => {}
+            _ => self.best = Some((epoch, val, self.coeffs)),
+        }
+
+        match (self.early_stopping, &self.best) {
+            (Some(s), Some((best_epoch, _, _))) if epoch - best_epoch > s.patience => {
+                if self.verbose {
+                    println!("Early stopping at epoch {}, no improvement since epoch {}", epoch, best_epoch);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slut::dimension::{Length, Time};
+    use slut::si::{Area, Velocity};
+
+    use super::*;
+    use crate::model::Polynomial;
+    use crate::optim::Sgd;
+    use crate::regularize::L1;
+    use crate::schedule::Constant;
+
+    fn trainer<Y: Dimensional + TrainingDims<L>, L: Dimensional>(data: Dataset) -> Trainer<Y, L> {
+        let mut t = Trainer::new(Box::new(Polynomial::new(3)), data, Box::new(Sgd), Box::new(Constant), Curriculum::off());
+        t.verbose = false;
+        t.plot_every = 0;
+        t
+    }
+
+    #[test]
+    fn trains_on_a_dimensioned_csv() {
+        let path = std::env::temp_dir().join(format!("slut-ml-train-dims-{}.csv", std::process::id()));
+        let rows: String = (0..50).map(|i| format!("{},{}\n", i * 20, 100.0 + 3.0 * i as f64)).collect();
+        std::fs::write(&path, format!("t[ms],h[cm]\n{}", rows)).unwrap();
+        let data = Dataset::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(data.y_dim, Length::DIM);
+
+        assert!(trainer::<Dimensionless, Dimensionless>(data.clone()).check_dims().is_err());
+        let mut t = trainer::<Length, Area>(data);
+        t.check_dims().unwrap();
+        let report = t.solve(Solver::GaussNewton, &SolveOptions::default());
+        assert!(report.converged);
+        assert!(t.data_loss(&t.coeffs).raw() < 1e-20);
+        // 1 m + 1.5 m/s · t
+        let c = to_array(&t.coeffs);
+        assert!((c[0] - 1.0).abs() < 1e-9 && (c[1] - 1.5).abs() < 1e-9);
+        let v: Scalar<f64, Velocity> = t.monomial_coeff::<Time, 1>().unwrap().unwrap();
+        assert!((v.raw() - 1.5).abs() < 1e-9);
+        assert!(t.monomial_coeff::<Length, 1>().is_err());
+    }
+
+    #[test]
+    fn loss_dimension_follows_its_degree() {
+        let data = Dataset::grid(|x| x, 0.0, 0.1, 10).with_dims(Time::DIM, Length::DIM);
+        let mut sq = trainer::<Length, Area>(data.clone());
+        let mut lin = trainer::<Length, Length>(data);
+        for name in ["mse", "huber"] {
+            sq.loss = crate::loss::by_name(name).unwrap();
+            lin.loss = crate::loss::by_name(name).unwrap();
+            sq.check_dims().unwrap();
+            assert!(lin.check_dims().is_err(), "{}", name);
+        }
+        for name in ["mae", "quantile", "maxerr"] {
+            sq.loss = crate::loss::by_name(name).unwrap();
+            lin.loss = crate::loss::by_name(name).unwrap();
+            lin.check_dims().unwrap();
+            assert!(sq.check_dims().is_err(), "{}", name);
+        }
+        // the rate and the clipping threshold follow the loss
+        let _: (Scalar<f64, Dimensionless>, Scalar<f64, Length>) = (sq.lr, sq.max_gnorm);
+        let _: (Scalar<f64, Length>, Scalar<f64, Dimensionless>) = (lin.lr, lin.max_gnorm);
+        lin.loss = crate::loss::by_name("logcosh").unwrap();
+        assert!(lin.check_dims().is_err());
+        assert_eq!(sq.format_loss(Scalar::default([2.0])), "2 m²");
+    }
+
+    #[test]
+    fn time_schedules_run_every_step() {
+        let mut t = trainer::<Dimensionless, Dimensionless>(Dataset::grid(|x| x, 0.0, 0.1, 20));
+        t.scheduler = Box::new(crate::schedule::Exponential { base_lr: 1e-3, gamma: 0.5 });
+        t.batcher = Some(Batcher::new(5, 1));
+        t.epochs = 2;
+        t.train();
+        assert_eq!(t.steps, 8);
+        assert!((t.lr.raw() - 1e-3 * 0.25).abs() < 1e-15);
+    }
+
+    // plain SGD recording the rate of every step
+    struct Rates(std::sync::Arc<std::sync::Mutex<Vec<f64>>>);
+
+    impl Optimizer for Rates {
+        fn step(&mut self, params: &mut Params, grads: &Params, lr: Scalar<f64, Dimensionless>) {
+            self.0.lock().unwrap().push(lr.raw());
+            Sgd.step(params, grads, lr);
+        }
+
+        fn name(&self) -> &'static str {
+            "rates"
+        }
+    }
+
+    #[test]
+    fn warmup_sets_the_first_rate() {
+        let rates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut t = trainer::<Dimensionless, Dimensionless>(Dataset::grid(|x| x, 0.0, 0.1, 20));
+        t.optimizer = Box::new(Rates(rates.clone()));
+        t.scheduler = Box::new(crate::schedule::Warmup::new(1e-2, 4, Box::new(Constant)));
+        t.batcher = Some(Batcher::new(10, 1));
+        t.epochs = 2;
+        t.train();
+        let want: Vec<f64> = [1.0, 1.5, 2.0, 2.5].iter().map(|k| 1e-2 * k / 4.0).collect();
+        let got = rates.lock().unwrap().clone();
+        assert_eq!(got.len(), want.len());
+        assert!(got.iter().zip(&want).all(|(g, w)| (g - w).abs() < 1e-15), "{:?}", got);
+    }
+
+    // validation targets moving away as training fits y = x
+    fn diverging() -> Trainer {
+        let mut t = trainer::<Dimensionless, Dimensionless>(Dataset::grid(|x| x, 0.0, 0.1, 20));
+        t.val = Some(Dataset::grid(|x| -x, 0.0, 0.1, 20));
+        t.lr = Scalar::default([1e-2]);
+        t
+    }
+
+    #[test]
+    fn early_stopping_restores_the_best_checkpoint() {
+        let mut t = diverging();
+        t.early_stopping = Some(EarlyStopping { patience: 3, min_delta: Scalar::zero() });
+        t.epochs = 100;
+        t.train();
+        let (epoch, _, best) = t.best.unwrap();
+        assert_eq!(epoch, 0);
+        assert_eq!(t.val_losses.len(), 5);
+        assert_eq!(to_array(&t.coeffs), to_array(&best));
+    }
+
+    #[test]
+    fn no_restore_without_an_early_stop() {
+        let mut t = diverging();
+        t.early_stopping = Some(EarlyStopping { patience: 1000, min_delta: Scalar::zero() });
+        t.epochs = 10;
+        t.train();
+        let (_, _, best) = t.best.unwrap();
+        assert_eq!(t.val_losses.len(), 10);
+        assert_ne!(to_array(&t.coeffs), to_array(&best));
+    }
+
+    // plain SGD counting its resets
+    struct Spy(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl Optimizer for Spy {
+        fn step(&mut self, params: &mut Params, grads: &Params, lr: Scalar<f64, Dimensionless>) {
+            Sgd.step(params, grads, lr);
+        }
+
+        fn reset(&mut self) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn name(&self) -> &'static str {
+            "spy"
+        }
+    }
+
+    #[test]
+    fn line_search_prox_uses_the_accepted_step() {
+        let l1 = L1 { lambda: 0.05 };
+        let mut t = trainer::<Dimensionless, Dimensionless>(Dataset::grid(|x| 1.0 + x, 0.0, 0.1, 20));
+        t.line_search = Some(LineSearch::armijo());
+        t.regularizers.push(Box::new(L1 { lambda: l1.lambda }));
+        let grads = t.step_grads(&t.data.clone());
+        let c0 = to_array(&t.coeffs);
+        t.apply(&grads);
+
+        // shrunk by step·λ, not by the unused learning rate
+        let step = t.last_step;
+        assert!(step > 1e-2, "{}", step);
+        let g = to_array(&grads);
+        let want = to_array(&l1.prox(&from_array(std::array::from_fn(|k| c0[k] - step * g[k])), dless!(step)));
+        for (got, want) in to_array(&t.coeffs).iter().zip(want) {
+            assert!((got - want).abs() < 1e-12, "{} vs {}", got, want);
+        }
+    }
+
+    #[test]
+    fn unlocks_reset_the_optimizer() {
+        let resets = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut t = trainer::<Dimensionless, Dimensionless>(Dataset::grid(|x| x, 0.0, 0.1, 20));
+        t.optimizer = Box::new(Spy(resets.clone()));
+        t.model = Box::new(Polynomial::new(5));
+        t.curriculum = Curriculum::epochs(2);
+        t.epochs = 7;
+        t.train();
+        // the third unlock would go past the model's five terms
+        assert_eq!(t.curriculum.events.iter().map(|u| u.terms).collect::<Vec<_>>(), [4, 5]);
+        assert_eq!(resets.load(std::sync::atomic::Ordering::Relaxed), 2);
+    }
+}